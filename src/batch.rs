@@ -0,0 +1,735 @@
+//! Solving puzzle collections as they are read, on one thread or a pool of
+//! them, with statistics for each puzzle and for the whole run
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{Board, DisplayStyle, RudokuError, TechniqueKind};
+
+/// Columns of `BatchResult::csv_row`
+pub const CSV_HEADER: &str = "index,puzzle,solutions,solution,micros,guesses,hardest,error";
+
+/// What solving one puzzle of a batch found
+#[derive(Debug, Clone)]
+pub struct BatchResult {
+    /// Position of the puzzle in the batch, from 0
+    pub index: usize,
+    /// `None` if the puzzle could not be read
+    pub puzzle: Option<Board>,
+    pub solution: Option<Board>,
+    /// Number of solutions, counting no further than 2
+    pub solutions: usize,
+    pub elapsed: Duration,
+    pub guesses: usize,
+    pub hardest: Option<TechniqueKind>,
+    /// Why the puzzle could not be read or solved
+    pub error: Option<RudokuError>,
+}
+
+impl BatchResult {
+    fn unreadable(index: usize, error: RudokuError) -> Self {
+        Self {
+            index,
+            puzzle: None,
+            solution: None,
+            solutions: 0,
+            elapsed: Duration::default(),
+            guesses: 0,
+            hardest: None,
+            error: Some(error),
+        }
+    }
+
+    fn from_puzzle(index: usize, puzzle: Result<Board, RudokuError>) -> Self {
+        match puzzle {
+            Ok(board) => Self::solve(index, board),
+            Err(error) => Self::unreadable(index, error),
+        }
+    }
+
+    /// Solve a copy of `board`, timing the logical solve and the count of
+    /// solutions together
+    pub fn solve(index: usize, board: Board) -> Self {
+        let start = Instant::now();
+        let report = board.report();
+        let solutions = match report {
+            Ok(_) => board.count_solutions(2),
+            Err(_) => 0,
+        };
+        let elapsed = start.elapsed();
+        let (solution, guesses, hardest, error) = match report {
+            Ok(report) => (
+                Some(report.solution),
+                report.rating.guesses,
+                report.rating.hardest,
+                None,
+            ),
+            Err(error) => (None, 0, None, Some(error)),
+        };
+        Self {
+            index,
+            puzzle: Some(board),
+            solution,
+            solutions,
+            elapsed,
+            guesses,
+            hardest,
+            error,
+        }
+    }
+
+    /// The result as a line of CSV, in the columns of `CSV_HEADER`
+    pub fn csv_row(&self) -> String {
+        let line = |board: &Option<Board>| {
+            board.as_ref().map_or(String::new(), |board| {
+                board.display(DisplayStyle::Line).to_string()
+            })
+        };
+        let error = self.error.as_ref().map_or(String::new(), |error| {
+            format!("\"{}\"", error.to_string().replace('"', "\"\""))
+        });
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            self.index,
+            line(&self.puzzle),
+            self.solutions,
+            line(&self.solution),
+            self.elapsed.as_micros(),
+            self.guesses,
+            self.hardest
+                .map_or(String::new(), |hardest| hardest.to_string()),
+            error
+        )
+    }
+
+    /// The result as a line of JSON, with the same fields as `csv_row` and
+    /// boards written as in `DisplayStyle::Line`
+    #[cfg(feature = "serde")]
+    pub fn json_row(&self) -> String {
+        let line = |board: &Option<Board>| {
+            board
+                .as_ref()
+                .map(|board| board.display(DisplayStyle::Line).to_string())
+        };
+        serde_json::json!({
+            "index": self.index,
+            "puzzle": line(&self.puzzle),
+            "solutions": self.solutions,
+            "solution": line(&self.solution),
+            "micros": self.elapsed.as_micros() as u64,
+            "guesses": self.guesses,
+            "hardest": self.hardest,
+            "error": self.error.as_ref().map(|error| error.to_string()),
+        })
+        .to_string()
+    }
+}
+
+/// Solve each board in turn
+pub fn solve_all<I>(boards: I) -> impl Iterator<Item = BatchResult>
+where
+    I: IntoIterator<Item = Board>,
+{
+    boards
+        .into_iter()
+        .enumerate()
+        .map(|(index, board)| BatchResult::solve(index, board))
+}
+
+/// The puzzles of an `.sdm` style file, one per line, read as they are
+/// needed. Blank lines and `#` comments are skipped, and anything after the
+/// puzzle on a line, such as ratings, is ignored.
+pub fn read_lines<R: BufRead>(
+    reader: R,
+) -> impl Iterator<Item = io::Result<Result<Board, RudokuError>>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            line.as_ref().map_or(true, |line| {
+                !line.trim().is_empty() && !line.starts_with('#')
+            })
+        })
+        .map(|(line_index, line)| {
+            line.map(|line| {
+                let puzzle = line.split_whitespace().next().unwrap_or("");
+                puzzle.parse().map_err(|error| RudokuError::AtLine {
+                    line: line_index + 1,
+                    error: Box::new(error),
+                })
+            })
+        })
+}
+
+/// Solve the puzzles of an `.sdm` style file as they are read, without
+/// holding the whole file in memory. Lines that can't be read as puzzles
+/// give results with an error; errors reading the file itself end the
+/// batch.
+pub fn solve_lines<R: BufRead>(reader: R) -> impl Iterator<Item = io::Result<BatchResult>> {
+    read_lines(reader)
+        .enumerate()
+        .map(|(index, puzzle)| puzzle.map(|puzzle| BatchResult::from_puzzle(index, puzzle)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParallelOptions {
+    /// Number of worker threads, or 0 for one per core
+    pub threads: usize,
+    /// Give results in the order of the puzzles rather than as they finish.
+    /// A slow puzzle then holds back the results after it.
+    pub ordered: bool,
+}
+
+impl Default for ParallelOptions {
+    fn default() -> Self {
+        Self {
+            threads: 0,
+            ordered: true,
+        }
+    }
+}
+
+impl ParallelOptions {
+    fn thread_count(self) -> usize {
+        match self.threads {
+            0 => thread::available_parallelism().map_or(1, |threads| threads.get()),
+            threads => threads,
+        }
+    }
+}
+
+/// A result, or the error that ended reading, with the position it had
+type Indexed = (usize, io::Result<BatchResult>);
+
+/// Results of a batch solved by a pool of threads, from `solve_parallel` or
+/// `solve_lines_parallel`. Dropping it stops the workers once they finish
+/// their current puzzles.
+pub struct ParallelResults {
+    results: Receiver<Indexed>,
+    ordered: bool,
+    next: usize,
+    // Results that finished ahead of `next`, in ordered mode
+    pending: BTreeMap<usize, io::Result<BatchResult>>,
+    // One ticket back to the reader for each result given out. The reader
+    // takes a ticket for each puzzle, so no more than `window` puzzles are
+    // ever between it and the caller, and `pending` stays that small.
+    tickets: Sender<()>,
+}
+
+/// Puzzles in flight per thread, whether queued, being solved or waiting
+/// for the results before them
+const WINDOW_PER_THREAD: usize = 4;
+
+impl ParallelResults {
+    fn start<I>(puzzles: I, options: ParallelOptions) -> Self
+    where
+        I: Iterator<Item = io::Result<Result<Board, RudokuError>>> + Send + 'static,
+    {
+        let threads = options.thread_count();
+        let window = threads * WINDOW_PER_THREAD;
+        // Bounded queues keep only a few puzzles per thread in memory
+        let (job_sender, jobs) = mpsc::sync_channel::<(usize, Result<Board, RudokuError>)>(window);
+        let (result_sender, results) = mpsc::sync_channel::<Indexed>(window);
+        let (tickets, ticket_receiver) = mpsc::channel::<()>();
+        (0..window).for_each(|_| {
+            let _ = tickets.send(());
+        });
+        let jobs = Arc::new(Mutex::new(jobs));
+        for _ in 0..threads {
+            let jobs = Arc::clone(&jobs);
+            let results = result_sender.clone();
+            thread::spawn(move || loop {
+                // Hold the lock only while taking a job
+                let job = match jobs.lock() {
+                    Ok(jobs) => jobs.recv(),
+                    Err(_) => break,
+                };
+                let (index, puzzle) = match job {
+                    Ok(job) => job,
+                    Err(_) => break,
+                };
+                let result = BatchResult::from_puzzle(index, puzzle);
+                if results.send((index, Ok(result))).is_err() {
+                    break;
+                }
+            });
+        }
+        thread::spawn(move || {
+            for (index, puzzle) in puzzles.enumerate() {
+                // Wait for the caller to take a result, or to stop
+                if ticket_receiver.recv().is_err() {
+                    break;
+                }
+                let sent = match puzzle {
+                    Ok(puzzle) => job_sender.send((index, puzzle)).is_ok(),
+                    Err(error) => {
+                        let _ = result_sender.send((index, Err(error)));
+                        false
+                    }
+                };
+                if !sent {
+                    break;
+                }
+            }
+        });
+        Self {
+            results,
+            ordered: options.ordered,
+            next: 0,
+            pending: BTreeMap::new(),
+            tickets,
+        }
+    }
+}
+
+impl Iterator for ParallelResults {
+    type Item = io::Result<BatchResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.next_result();
+        if result.is_some() {
+            // The reader may have finished, and then nobody needs a ticket
+            let _ = self.tickets.send(());
+        }
+        result
+    }
+}
+
+impl ParallelResults {
+    fn next_result(&mut self) -> Option<io::Result<BatchResult>> {
+        if !self.ordered {
+            return self.results.recv().ok().map(|(_, result)| result);
+        }
+        loop {
+            if let Some(result) = self.pending.remove(&self.next) {
+                self.next += 1;
+                return Some(result);
+            }
+            match self.results.recv() {
+                Ok((index, result)) => {
+                    self.pending.insert(index, result);
+                }
+                // Every thread has finished, so whatever is left is in order
+                Err(_) => {
+                    let (index, result) = self.pending.pop_first()?;
+                    self.next = index + 1;
+                    return Some(result);
+                }
+            }
+        }
+    }
+}
+
+/// Solve boards on a pool of threads, taking them from `boards` only as
+/// fast as they are solved
+pub fn solve_parallel<I>(boards: I, options: ParallelOptions) -> impl Iterator<Item = BatchResult>
+where
+    I: IntoIterator<Item = Board>,
+    I::IntoIter: Send + 'static,
+{
+    ParallelResults::start(boards.into_iter().map(|board| Ok(Ok(board))), options)
+        // Only reading a file can fail, and boards are not read
+        .filter_map(Result::ok)
+}
+
+/// Solve the puzzles of an `.sdm` style file on a pool of threads as they
+/// are read, as `solve_lines` does on one
+pub fn solve_lines_parallel<R>(reader: R, options: ParallelOptions) -> ParallelResults
+where
+    R: BufRead + Send + 'static,
+{
+    ParallelResults::start(read_lines(reader), options)
+}
+
+/// Totals over the results of a batch
+#[derive(Debug, Clone)]
+pub struct BatchSummary {
+    pub puzzles: usize,
+    /// Puzzles with exactly one solution
+    pub solved: usize,
+    pub multiple: usize,
+    /// Puzzles with no solution, including those with conflicting givens
+    pub unsolvable: usize,
+    /// Lines that could not be read as puzzles
+    pub unreadable: usize,
+    pub guesses: usize,
+    /// Time since the summary was created, up to the last result added
+    pub elapsed: Duration,
+    started: Instant,
+    times: Histogram,
+}
+
+impl BatchSummary {
+    /// Start timing a batch
+    pub fn new() -> Self {
+        Self {
+            puzzles: 0,
+            solved: 0,
+            multiple: 0,
+            unsolvable: 0,
+            unreadable: 0,
+            guesses: 0,
+            elapsed: Duration::default(),
+            started: Instant::now(),
+            times: Histogram::new(),
+        }
+    }
+
+    pub fn add(&mut self, result: &BatchResult) {
+        self.puzzles += 1;
+        match (&result.puzzle, result.solutions) {
+            (None, _) => self.unreadable += 1,
+            (Some(_), 0) => self.unsolvable += 1,
+            (Some(_), 1) => self.solved += 1,
+            (Some(_), _) => self.multiple += 1,
+        }
+        self.guesses += result.guesses;
+        if result.puzzle.is_some() {
+            self.times.add(result.elapsed);
+        }
+        self.elapsed = self.started.elapsed();
+    }
+
+    /// Puzzles that were unreadable or had no solution
+    pub fn failures(&self) -> usize {
+        self.unreadable + self.unsolvable
+    }
+
+    /// Puzzles finished per second of the batch
+    pub fn throughput(&self) -> f64 {
+        match self.elapsed.as_secs_f64() {
+            secs if secs > 0.0 => self.puzzles as f64 / secs,
+            _ => 0.0,
+        }
+    }
+
+    /// The time to solve a puzzle at each percentile, from 0 to 100, by the
+    /// nearest rank. Times are kept in buckets, so these are within about
+    /// 6% of the exact times, apart from the fastest and slowest.
+    pub fn percentiles(&self, percentiles: &[f64]) -> Vec<Duration> {
+        percentiles
+            .iter()
+            .map(|percentile| self.times.percentile(*percentile))
+            .collect()
+    }
+}
+
+/// Buckets per doubling of the time
+const SUB_BUCKETS: u64 = 8;
+/// Enough buckets for any number of microseconds that fits a `u64`
+const BUCKETS: usize = (64 - 2) * SUB_BUCKETS as usize;
+
+/// Counts of times in buckets spaced on a log scale, so that a summary
+/// takes the same space however many puzzles it covers. Times under 8µs
+/// get a bucket each, and above that each doubling is split in eight.
+#[derive(Debug, Clone)]
+struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+    min: Duration,
+    max: Duration,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            counts: vec![0; BUCKETS],
+            total: 0,
+            min: Duration::MAX,
+            max: Duration::ZERO,
+        }
+    }
+
+    fn bucket(micros: u64) -> usize {
+        if micros < SUB_BUCKETS {
+            return micros as usize;
+        }
+        let doubling = 63 - micros.leading_zeros() as u64;
+        let step = doubling - 3;
+        ((doubling - 2) * SUB_BUCKETS + ((micros >> step) & (SUB_BUCKETS - 1))) as usize
+    }
+
+    /// The middle of a bucket, in microseconds
+    fn middle(bucket: usize) -> u64 {
+        let bucket = bucket as u64;
+        if bucket < SUB_BUCKETS {
+            return bucket;
+        }
+        let step = bucket / SUB_BUCKETS - 1;
+        let low = (SUB_BUCKETS + bucket % SUB_BUCKETS) << step;
+        low + (1 << step) / 2
+    }
+
+    fn add(&mut self, time: Duration) {
+        let micros = time.as_micros().min(u64::MAX as u128) as u64;
+        self.counts[Self::bucket(micros)] += 1;
+        self.total += 1;
+        self.min = self.min.min(time);
+        self.max = self.max.max(time);
+    }
+
+    fn percentile(&self, percentile: f64) -> Duration {
+        if self.total == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((percentile / 100.0 * self.total as f64).ceil() as u64).clamp(1, self.total);
+        if rank == 1 {
+            return self.min;
+        }
+        if rank == self.total {
+            return self.max;
+        }
+        let mut seen = 0;
+        let bucket = self
+            .counts
+            .iter()
+            .position(|count| {
+                seen += count;
+                seen >= rank
+            })
+            .unwrap_or(BUCKETS - 1);
+        Duration::from_micros(Self::middle(bucket)).clamp(self.min, self.max)
+    }
+}
+
+impl Default for BatchSummary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extend<BatchResult> for BatchSummary {
+    fn extend<I: IntoIterator<Item = BatchResult>>(&mut self, results: I) {
+        results.into_iter().for_each(|result| self.add(&result));
+    }
+}
+
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} puzzles: {} solved, {} with more than one solution, {} unsolvable, {} unreadable",
+            self.puzzles, self.solved, self.multiple, self.unsolvable, self.unreadable
+        )?;
+        writeln!(
+            f,
+            "{:.3}s, {:.1} puzzles/s, {} guesses",
+            self.elapsed.as_secs_f64(),
+            self.throughput(),
+            self.guesses
+        )?;
+        let times = self.percentiles(&[50.0, 90.0, 99.0, 100.0]);
+        let ms = |time: Duration| time.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "per puzzle: p50 {:.3}ms, p90 {:.3}ms, p99 {:.3}ms, max {:.3}ms",
+            ms(times[0]),
+            ms(times[1]),
+            ms(times[2]),
+            ms(times[3])
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sudoku;
+
+    const PUZZLE: &str =
+        "379000014060010070080009005435007000090040020000800436900700080040080050850000249";
+    /// The solution of `PUZZLE` with four cells cleared whose values can
+    /// swap
+    const TWO_SOLUTIONS: &str =
+        "079026814064018972281479365435267198698143527712895436923754681146982753857631249";
+
+    #[test]
+    fn solve_all_reports_each_puzzle() {
+        let boards = vec![
+            Board::from_string(PUZZLE).unwrap(),
+            Board::from_string(TWO_SOLUTIONS).unwrap(),
+            Board::from_string(&PUZZLE.replacen('0', "2", 1)).unwrap(),
+        ];
+        let results = solve_all(boards).collect::<Vec<BatchResult>>();
+        assert_eq!(results[0].solutions, 1);
+        assert_eq!(results[0].guesses, 0);
+        assert_eq!(results[0].hardest, Some(TechniqueKind::HiddenSingle));
+        assert!(results[0].solution.as_ref().unwrap().is_solved());
+        assert_eq!(results[1].solutions, 2);
+        assert!(results[1].guesses > 0);
+        assert_eq!(results[2].solutions, 0);
+        assert!(results[2].error.is_some());
+
+        let mut summary = BatchSummary::new();
+        summary.extend(results);
+        assert_eq!(
+            (
+                summary.puzzles,
+                summary.solved,
+                summary.multiple,
+                summary.unsolvable
+            ),
+            (3, 1, 1, 1)
+        );
+        assert_eq!(summary.failures(), 1);
+    }
+
+    #[test]
+    fn solve_lines_reads_as_it_goes() {
+        let text = format!(
+            "# puzzles\n{} ED=1.5/1.5/1.5\n\n{}x\n",
+            PUZZLE,
+            &PUZZLE[1..]
+        );
+        let results = solve_lines(text.as_bytes())
+            .collect::<io::Result<Vec<BatchResult>>>()
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].solutions, 1);
+        assert!(results[0]
+            .csv_row()
+            .starts_with(&format!("0,{},1,379526814", PUZZLE)));
+        assert_eq!(results[1].index, 1);
+        assert!(results[1].puzzle.is_none());
+        assert!(matches!(
+            results[1].error,
+            Some(RudokuError::AtLine { line: 4, .. })
+        ));
+        assert!(results[1]
+            .csv_row()
+            .ends_with("\"line 4: unexpected character 'x' at line 1, column 81\""));
+    }
+
+    #[test]
+    fn boards_can_be_solved_on_other_threads() {
+        fn assert_send<T: Send + Sync>() {}
+        assert_send::<Board>();
+        assert_send::<BatchResult>();
+    }
+
+    type Outcome = (usize, usize, Option<Vec<Vec<i32>>>);
+
+    fn outcome(result: BatchResult) -> Outcome {
+        let solution = result.solution.map(|board| board.rows());
+        (result.index, result.solutions, solution)
+    }
+
+    #[test]
+    fn parallel_results_match_sequential() {
+        let boards = (0..24)
+            .map(|index| match index % 3 {
+                0 => Board::from_string(PUZZLE).unwrap(),
+                1 => Board::from_string(TWO_SOLUTIONS).unwrap(),
+                _ => crate::generate::generate(&crate::generate::GeneratorOptions {
+                    seed: index,
+                    clues: 40,
+                    ..Default::default()
+                })
+                .unwrap(),
+            })
+            .collect::<Vec<Board>>();
+        let expected = solve_all(boards.clone())
+            .map(outcome)
+            .collect::<Vec<Outcome>>();
+        let options = ParallelOptions {
+            threads: 4,
+            ordered: true,
+        };
+        let ordered = solve_parallel(boards.clone(), options)
+            .map(outcome)
+            .collect::<Vec<Outcome>>();
+        assert_eq!(ordered, expected);
+
+        let mut unordered = solve_parallel(
+            boards,
+            ParallelOptions {
+                ordered: false,
+                ..options
+            },
+        )
+        .map(outcome)
+        .collect::<Vec<Outcome>>();
+        unordered.sort();
+        assert_eq!(unordered, expected);
+    }
+
+    #[test]
+    fn ordered_results_wait_in_a_bounded_window() {
+        // A puzzle that takes a search, ahead of many that do not
+        let boards = std::iter::once(Board::from_string(TWO_SOLUTIONS).unwrap())
+            .chain((0..200).map(|_| Board::from_string(PUZZLE).unwrap()));
+        let threads = 2;
+        let mut results = ParallelResults::start(
+            boards.map(|board| Ok(Ok(board))),
+            ParallelOptions {
+                threads,
+                ordered: true,
+            },
+        );
+        let mut count = 0;
+        while let Some(result) = results.next() {
+            assert_eq!(result.unwrap().index, count);
+            assert!(results.pending.len() <= threads * WINDOW_PER_THREAD);
+            count += 1;
+        }
+        assert_eq!(count, 201);
+    }
+
+    #[test]
+    fn parallel_lines_keep_unreadable_puzzles() {
+        let text = format!("{}\nnonsense\n{}\n", PUZZLE, TWO_SOLUTIONS);
+        let results = solve_lines_parallel(
+            io::Cursor::new(text),
+            ParallelOptions {
+                threads: 2,
+                ordered: true,
+            },
+        )
+        .collect::<io::Result<Vec<BatchResult>>>()
+        .unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| result.solutions)
+                .collect::<Vec<usize>>(),
+            vec![1, 0, 2]
+        );
+        assert!(results[1].puzzle.is_none());
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let mut summary = BatchSummary::new();
+        (1..=10).for_each(|ms| {
+            let mut result = BatchResult::solve(0, Board::from_string(PUZZLE).unwrap());
+            result.elapsed = Duration::from_millis(ms);
+            summary.add(&result);
+        });
+        let times = summary.percentiles(&[0.0, 50.0, 90.0, 100.0]);
+        assert_eq!(times[0], Duration::from_millis(1));
+        assert_eq!(times[3], Duration::from_millis(10));
+        [5, 9].iter().zip(&times[1..3]).for_each(|(ms, time)| {
+            let error = time.as_secs_f64() * 1000.0 / *ms as f64 - 1.0;
+            assert!(error.abs() < 0.07, "{:?} for {}ms", time, ms);
+        });
+        assert!(BatchSummary::new().percentiles(&[50.0])[0].is_zero());
+    }
+
+    #[test]
+    fn histogram_buckets_cover_every_time() {
+        // The middle of each bucket falls in it
+        (0..BUCKETS).for_each(|bucket| {
+            assert_eq!(Histogram::bucket(Histogram::middle(bucket)), bucket);
+        });
+        assert_eq!(Histogram::bucket(u64::MAX), BUCKETS - 1);
+        let mut histogram = Histogram::new();
+        (0..100_000).for_each(|micros| histogram.add(Duration::from_micros(micros)));
+        assert_eq!(histogram.counts.len(), BUCKETS);
+        assert_eq!(histogram.percentile(100.0), Duration::from_micros(99_999));
+    }
+}
@@ -0,0 +1,426 @@
+//! Encoding boards as DIMACS CNF for external SAT solvers, and reading their
+//! models back as solutions.
+//!
+//! On a board of size `n`, the variable `(row * n + col) * n + value` is
+//! true when the cell at `row` and `col`, counting from 0, holds `value`.
+//! Every cell holds exactly one value, every unit holds each value exactly
+//! once, and each given is a clause of its own.
+//!
+//! Variants add constraints to the encoding, and `decode` checks a model
+//! against the same variants, so it should be given those the board was
+//! encoded with.
+
+use std::fmt;
+
+use crate::{Board, RudokuError, Shape, Unit};
+
+/// Constraints beyond those of standard Sudoku
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Variant {
+    /// Both main diagonals hold each value once, as in X-Sudoku
+    Diagonals,
+    /// Cells a chess knight's move apart hold different values
+    AntiKnight,
+    /// Cells that touch at a corner hold different values
+    AntiKing,
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Variant::Diagonals => "diagonals",
+            Variant::AntiKnight => "anti-knight",
+            Variant::AntiKing => "anti-king",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Variant {
+    /// Groups of cells that must hold each value once
+    fn units(self, size: usize) -> Vec<Vec<(usize, usize)>> {
+        match self {
+            Variant::Diagonals => vec![
+                (0..size).map(|i| (i, i)).collect(),
+                (0..size).map(|i| (i, size - 1 - i)).collect(),
+            ],
+            _ => vec![],
+        }
+    }
+
+    /// Pairs of cells that must hold different values
+    fn pairs(self, size: usize) -> Vec<((usize, usize), (usize, usize))> {
+        let offsets: &[(isize, isize)] = match self {
+            Variant::Diagonals => &[],
+            Variant::AntiKnight => &[(1, -2), (1, 2), (2, -1), (2, 1)],
+            Variant::AntiKing => &[(1, -1), (1, 1)],
+        };
+        let mut pairs = Vec::new();
+        for row_index in 0..size {
+            for col_index in 0..size {
+                offsets.iter().for_each(|(down, across)| {
+                    let row = row_index as isize + down;
+                    let col = col_index as isize + across;
+                    if (0..size as isize).contains(&row) && (0..size as isize).contains(&col) {
+                        pairs.push(((row_index, col_index), (row as usize, col as usize)));
+                    }
+                });
+            }
+        }
+        pairs
+    }
+}
+
+/// The variable for `value` in a cell
+pub fn variable(shape: Shape, row_index: usize, col_index: usize, value: i32) -> i32 {
+    ((row_index * shape.size() + col_index) * shape.size()) as i32 + value
+}
+
+/// A formula in conjunctive normal form, as clauses of non-zero literals.
+/// Displays in DIMACS format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cnf {
+    pub shape: Shape,
+    pub variables: usize,
+    pub clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+    /// Encode the givens of a board and the rules of Sudoku, along with
+    /// any variants
+    pub fn encode(board: &Board, variants: &[Variant]) -> Self {
+        let shape = board.shape();
+        let size = shape.size();
+        let var = |(row_index, col_index): (usize, usize), value: i32| {
+            variable(shape, row_index, col_index, value)
+        };
+        let values = 1..=size as i32;
+        let mut clauses = Vec::new();
+        let mut exactly_one = |literals: Vec<i32>| {
+            for (i, a) in literals.iter().enumerate() {
+                for b in &literals[i + 1..] {
+                    clauses.push(vec![-a, -b]);
+                }
+            }
+            clauses.push(literals);
+        };
+
+        for row_index in 0..size {
+            for col_index in 0..size {
+                exactly_one(
+                    values
+                        .clone()
+                        .map(|value| var((row_index, col_index), value))
+                        .collect(),
+                );
+            }
+        }
+        let units = Unit::all(shape)
+            .into_iter()
+            .map(|unit| unit.cells(shape))
+            .chain(variants.iter().flat_map(|variant| variant.units(size)))
+            .collect::<Vec<Vec<(usize, usize)>>>();
+        for cells in &units {
+            for value in values.clone() {
+                exactly_one(cells.iter().map(|cell| var(*cell, value)).collect());
+            }
+        }
+        for variant in variants {
+            for (a, b) in variant.pairs(size) {
+                values
+                    .clone()
+                    .for_each(|value| clauses.push(vec![-var(a, value), -var(b, value)]));
+            }
+        }
+        for row_index in 0..size {
+            for col_index in 0..size {
+                match board.get_value(row_index, col_index) {
+                    0 => {}
+                    value => clauses.push(vec![var((row_index, col_index), value)]),
+                }
+            }
+        }
+        Self {
+            shape,
+            variables: shape.cell_count() * size,
+            clauses,
+        }
+    }
+}
+
+impl fmt::Display for Cnf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "c rudoku {} boxes", self.shape)?;
+        writeln!(f, "p cnf {} {}", self.variables, self.clauses.len())?;
+        self.clauses.iter().try_for_each(|clause| {
+            clause
+                .iter()
+                .try_for_each(|literal| write!(f, "{} ", literal))?;
+            writeln!(f, "0")
+        })
+    }
+}
+
+/// Read a SAT solver's output for a board encoded by `Cnf::encode` with
+/// `variants`. Both the competition format, with `s` and `v` lines, and the
+/// bare list of literals written by MiniSat are understood.
+pub fn decode(board: &Board, model: &str, variants: &[Variant]) -> Result<Board, RudokuError> {
+    let shape = board.shape();
+    let size = shape.size();
+    let mut values = vec![Vec::new(); shape.cell_count()];
+    let mut position = 0;
+    for line in model.lines().map(str::trim) {
+        if line.starts_with('c') || line.is_empty() {
+            continue;
+        }
+        let line = line.strip_prefix("s ").unwrap_or(line);
+        if line.starts_with("UNSAT") {
+            return Err(RudokuError::Unsolvable);
+        }
+        if line.starts_with("SAT") {
+            continue;
+        }
+        let literals = line.strip_prefix('v').unwrap_or(line);
+        for token in literals.split_whitespace() {
+            position += 1;
+            let literal = token
+                .parse::<i32>()
+                .map_err(|_| RudokuError::InvalidToken {
+                    token: token.to_string(),
+                    position,
+                })?;
+            let index = literal as usize;
+            if literal > 0 && index <= shape.cell_count() * size {
+                values[(index - 1) / size].push((index - 1) % size + 1);
+            }
+        }
+    }
+
+    let mut solution = board.clone();
+    for (index, cell_values) in values.iter().enumerate() {
+        let (row_index, col_index) = (index / size, index % size);
+        let given = board.get_value(row_index, col_index);
+        match cell_values.as_slice() {
+            [value] if given == 0 => solution.fill_cell(row_index, col_index, *value as i32),
+            [value] if given == *value as i32 => {}
+            _ => {
+                return Err(RudokuError::InvalidModel {
+                    row: row_index,
+                    col: col_index,
+                })
+            }
+        }
+    }
+    solution.refresh_candidates();
+    if let Some(conflict) = solution.conflicts().into_iter().next() {
+        return Err(RudokuError::DuplicateValue {
+            unit: conflict.unit,
+            value: conflict.value,
+        });
+    }
+    match variants
+        .iter()
+        .find_map(|variant| broken_by(&solution, *variant).map(|cell| (*variant, cell)))
+    {
+        Some((variant, (row, col))) => Err(RudokuError::VariantConflict { variant, row, col }),
+        None => Ok(solution),
+    }
+}
+
+/// The first cell of a solution to repeat a value that a variant keeps apart
+fn broken_by(solution: &Board, variant: Variant) -> Option<(usize, usize)> {
+    let size = solution.size();
+    let value = |(row_index, col_index): (usize, usize)| solution.get_value(row_index, col_index);
+    variant
+        .units(size)
+        .iter()
+        .find_map(|cells| {
+            (1..cells.len()).find_map(|i| {
+                cells[..i]
+                    .iter()
+                    .any(|cell| value(*cell) == value(cells[i]))
+                    .then(|| cells[i])
+            })
+        })
+        .or_else(|| {
+            variant
+                .pairs(size)
+                .into_iter()
+                .find(|(a, b)| value(*a) == value(*b))
+                .map(|(_, b)| b)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sudoku;
+
+    const PUZZLE: &str =
+        "379000014060010070080009005435007000090040020000800436900700080040080050850000249";
+
+    /// Plain DPLL with unit propagation, standing in for a SAT solver. The
+    /// model is written in the competition format.
+    fn dpll(cnf: &Cnf) -> String {
+        fn propagate(clauses: &[Vec<i32>], assignment: &mut [Option<bool>]) -> bool {
+            let value = |assignment: &[Option<bool>], literal: i32| {
+                assignment[literal.unsigned_abs() as usize].map(|value| value == (literal > 0))
+            };
+            loop {
+                let mut changed = false;
+                for clause in clauses {
+                    if clause
+                        .iter()
+                        .any(|literal| value(assignment, *literal) == Some(true))
+                    {
+                        continue;
+                    }
+                    let open = clause
+                        .iter()
+                        .filter(|literal| value(assignment, **literal).is_none())
+                        .collect::<Vec<&i32>>();
+                    match open.as_slice() {
+                        [] => return false,
+                        [literal] => {
+                            assignment[literal.unsigned_abs() as usize] = Some(**literal > 0);
+                            changed = true;
+                        }
+                        _ => {}
+                    }
+                }
+                if !changed {
+                    return true;
+                }
+            }
+        }
+
+        fn search(
+            clauses: &[Vec<i32>],
+            mut assignment: Vec<Option<bool>>,
+        ) -> Option<Vec<Option<bool>>> {
+            if !propagate(clauses, &mut assignment) {
+                return None;
+            }
+            let open = match (1..assignment.len()).find(|var| assignment[*var].is_none()) {
+                Some(open) => open,
+                None => return Some(assignment),
+            };
+            [true, false].iter().find_map(|choice| {
+                let mut branch = assignment.clone();
+                branch[open] = Some(*choice);
+                search(clauses, branch)
+            })
+        }
+
+        match search(&cnf.clauses, vec![None; cnf.variables + 1]) {
+            None => "s UNSATISFIABLE\n".to_string(),
+            Some(assignment) => {
+                let literals = (1..assignment.len())
+                    .map(|var| match assignment[var] {
+                        Some(true) => var.to_string(),
+                        _ => format!("-{}", var),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                format!("s SATISFIABLE\nv {} 0\n", literals)
+            }
+        }
+    }
+
+    #[test]
+    fn writes_dimacs() {
+        let board = Board::from_string(PUZZLE).unwrap();
+        let cnf = Cnf::encode(&board, &[]);
+        let text = cnf.to_string();
+        let header = format!("p cnf 729 {}", cnf.clauses.len());
+        assert_eq!(text.lines().nth(1), Some(header.as_str()));
+        assert_eq!(text.lines().count(), cnf.clauses.len() + 2);
+        // The first given, 3 in r1c1, is a clause of its own
+        assert_eq!(text.lines().filter(|line| *line == "3 0").count(), 1);
+        assert_eq!(variable(board.shape(), 8, 8, 9), 729);
+    }
+
+    #[test]
+    fn round_trips_through_a_sat_solver() {
+        let board = Board::from_string(PUZZLE).unwrap();
+        let solution = decode(&board, &dpll(&Cnf::encode(&board, &[])), &[]).unwrap();
+        let mut expected = board.clone();
+        expected.solve().unwrap();
+        assert_eq!(solution.rows(), expected.rows());
+
+        let unsolvable = Board::from_string(&PUZZLE.replacen('0', "2", 1)).unwrap();
+        assert_eq!(
+            decode(&unsolvable, &dpll(&Cnf::encode(&unsolvable, &[])), &[]).err(),
+            Some(RudokuError::Unsolvable)
+        );
+    }
+
+    #[test]
+    fn variants_add_constraints() {
+        let board = Board::with_shape(Shape::new(2, 2).unwrap());
+        let solve =
+            |variants: &[Variant]| decode(&board, &dpll(&Cnf::encode(&board, variants)), variants);
+
+        let solution = solve(&[Variant::Diagonals]).unwrap();
+        assert!(solution.is_solved());
+        let diagonal = (0..4)
+            .map(|i| solution.get_value(i, i))
+            .collect::<Vec<i32>>();
+        let anti = (0..4)
+            .map(|i| solution.get_value(i, 3 - i))
+            .collect::<Vec<i32>>();
+        assert!(crate::segment_valid(&diagonal) && crate::segment_valid(&anti));
+
+        let solution = solve(&[Variant::AntiKnight]).unwrap();
+        Variant::AntiKnight.pairs(4).iter().for_each(|(a, b)| {
+            assert_ne!(solution.get_value(a.0, a.1), solution.get_value(b.0, b.1));
+        });
+
+        // Of the 48 grids with distinct diagonals and the 24 without equal
+        // values a knight's move apart, none are both, and every 4x4 grid
+        // has equal values touching at a corner
+        assert_eq!(
+            solve(&[Variant::Diagonals, Variant::AntiKnight]).err(),
+            Some(RudokuError::Unsolvable)
+        );
+        assert_eq!(
+            solve(&[Variant::AntiKing]).err(),
+            Some(RudokuError::Unsolvable)
+        );
+    }
+
+    #[test]
+    fn rejects_models_that_do_not_fit() {
+        let board = Board::from_string(PUZZLE).unwrap();
+        assert_eq!(
+            decode(&board, "SAT\n1 2 0\n", &[]).err(),
+            Some(RudokuError::InvalidModel { row: 0, col: 0 })
+        );
+        assert!(matches!(
+            decode(&board, "v 1 x 0", &[]).err(),
+            Some(RudokuError::InvalidToken { position: 2, .. })
+        ));
+
+        // A model of the plain rules need not keep to a variant
+        let plain = Board::with_shape(Shape::new(2, 2).unwrap());
+        let model = (0..16)
+            .flat_map(|index| {
+                let value = [1, 2, 3, 4, 3, 4, 1, 2, 2, 1, 4, 3, 4, 3, 2, 1][index];
+                (1..=4).map(move |v| match v == value {
+                    true => (index * 4 + v).to_string(),
+                    false => format!("-{}", index * 4 + v),
+                })
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+        assert!(decode(&plain, &model, &[]).is_ok());
+        assert_eq!(
+            decode(&plain, &model, &[Variant::Diagonals]).err(),
+            Some(RudokuError::VariantConflict {
+                variant: Variant::Diagonals,
+                row: 2,
+                col: 2
+            })
+        );
+    }
+}
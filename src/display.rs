@@ -0,0 +1,211 @@
+use std::fmt;
+
+use crate::{value_char, Board, Sudoku};
+
+/// The ways a board can be printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayStyle {
+    /// Space-separated values, one row per line, 0 for blanks. Used by `{}`.
+    Plain,
+    /// Rows drawn inside `+---+` box borders, `.` for blanks. Used by `{:#}`.
+    Grid,
+    /// Every cell on one line, 0 for blanks, as read by `Board::from_string`
+    Line,
+    /// The possible values of each cell laid out in a small grid of their
+    /// own, inside box borders
+    Pencilmarks,
+}
+
+/// A board paired with the style to print it in, from `Board::display`
+pub struct BoardDisplay<'a> {
+    board: &'a Board,
+    style: DisplayStyle,
+}
+
+impl Board {
+    pub fn display(&self, style: DisplayStyle) -> BoardDisplay<'_> {
+        BoardDisplay { board: self, style }
+    }
+}
+
+impl BoardDisplay<'_> {
+    fn plain(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self
+            .board
+            .rows()
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|value| value_char(*value).to_string())
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            })
+            .collect::<Vec<String>>();
+        write!(f, "{}", rows.join("\n"))
+    }
+
+    fn line(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.board
+            .rows()
+            .iter()
+            .flatten()
+            .try_for_each(|value| write!(f, "{}", value_char(*value)))
+    }
+
+    /// Draw the board in box borders, with each cell given as `height` lines
+    /// of the same width
+    fn boxed<F>(&self, f: &mut fmt::Formatter<'_>, height: usize, cell: F) -> fmt::Result
+    where
+        F: Fn(usize, usize) -> Vec<String>,
+    {
+        let shape = self.board.shape();
+        let size = shape.size();
+        let width = cell(0, 0)[0].chars().count();
+        let border = format!(
+            "+{}",
+            format!("{}+", "-".repeat((width + 1) * shape.box_cols() + 1))
+                .repeat(size / shape.box_cols())
+        );
+        let mut lines = vec![border.clone()];
+        for row_index in 0..size {
+            let cells = (0..size)
+                .map(|col_index| cell(row_index, col_index))
+                .collect::<Vec<Vec<String>>>();
+            for line in 0..height {
+                let boxes = cells
+                    .chunks(shape.box_cols())
+                    .map(|chunk| {
+                        chunk
+                            .iter()
+                            .map(|lines| lines[line].as_str())
+                            .collect::<Vec<&str>>()
+                            .join(" ")
+                    })
+                    .collect::<Vec<String>>();
+                lines.push(format!("| {} |", boxes.join(" | ")));
+            }
+            if (row_index + 1) % shape.box_rows() == 0 {
+                lines.push(border.clone());
+            } else if height > 1 {
+                // Keep the small grids of neighbouring rows apart
+                let blank = " ".repeat((width + 1) * shape.box_cols() - 1);
+                let boxes = vec![blank; size / shape.box_cols()];
+                lines.push(format!("| {} |", boxes.join(" | ")));
+            }
+        }
+        write!(f, "{}", lines.join("\n"))
+    }
+
+    fn grid(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.boxed(f, 1, |row_index, col_index| {
+            let value = match self.board.get_value(row_index, col_index) {
+                0 => '.',
+                value => value_char(value),
+            };
+            vec![value.to_string()]
+        })
+    }
+
+    fn pencilmarks(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shape = self.board.shape();
+        let possible = self.board.possible_values();
+        self.boxed(f, shape.box_rows(), |row_index, col_index| {
+            let values = &possible[&(row_index, col_index)];
+            (0..shape.box_rows())
+                .map(|line| {
+                    (1..=shape.box_cols())
+                        .map(|k| (line * shape.box_cols() + k) as i32)
+                        .map(|value| match values.contains(&value) {
+                            true => value_char(value),
+                            false => '.',
+                        })
+                        .collect()
+                })
+                .collect()
+        })
+    }
+}
+
+impl fmt::Display for BoardDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.style {
+            DisplayStyle::Plain => self.plain(f),
+            DisplayStyle::Grid => self.grid(f),
+            DisplayStyle::Line => self.line(f),
+            DisplayStyle::Pencilmarks => self.pencilmarks(f),
+        }
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let style = match f.alternate() {
+            true => DisplayStyle::Grid,
+            false => DisplayStyle::Plain,
+        };
+        self.display(style).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Shape;
+
+    const PUZZLE: &str =
+        "379000014060010070080009005435007000090040020000800436900700080040080050850000249";
+
+    #[test]
+    fn plain_is_the_default() {
+        let board = Board::from_string(PUZZLE).unwrap();
+        let plain = board.to_string();
+        assert_eq!(plain.lines().next(), Some("3 7 9 0 0 0 0 1 4"));
+        assert_eq!(plain.lines().count(), 9);
+        assert_eq!(board.display(DisplayStyle::Plain).to_string(), plain);
+    }
+
+    #[test]
+    fn grid_draws_box_borders() {
+        let board = Board::from_string(PUZZLE).unwrap();
+        let grid = format!("{:#}", board);
+        let lines = grid.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 13);
+        assert_eq!(lines[0], "+-------+-------+-------+");
+        assert_eq!(lines[1], "| 3 7 9 | . . . | . 1 4 |");
+        assert_eq!(lines[4], "+-------+-------+-------+");
+        assert_eq!(board.display(DisplayStyle::Grid).to_string(), grid);
+
+        let board = Board::from_string("100000020000003000000400000050000006").unwrap();
+        let grid = format!("{:#}", board);
+        assert_eq!(grid.lines().next(), Some("+-------+-------+"));
+        assert_eq!(grid.lines().nth(1), Some("| 1 . . | . . . |"));
+        assert_eq!(grid.lines().count(), 10);
+    }
+
+    #[test]
+    fn line_round_trips() {
+        let board = Board::from_string(PUZZLE).unwrap();
+        let line = board.display(DisplayStyle::Line).to_string();
+        assert_eq!(line, PUZZLE);
+
+        let board = crate::generate::complete_grid(Shape::new(4, 4).unwrap(), 2);
+        let line = board.display(DisplayStyle::Line).to_string();
+        assert_eq!(line.len(), 256);
+        assert_eq!(Board::from_string(&line).unwrap().rows(), board.rows());
+    }
+
+    #[test]
+    fn pencilmarks_show_possible_values() {
+        let board = Board::from_string(PUZZLE).unwrap();
+        let marks = board.display(DisplayStyle::Pencilmarks).to_string();
+        let lines = marks.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 4 + 9 * 3 + 6);
+        assert_eq!(lines[0], "+-------------+-------------+-------------+");
+        // r2c1 can be 2 or 5, r2c2 holds 6 and r2c3 can be 2 or 4
+        assert_eq!(&lines[5][..15], "| .2. ... .2. |");
+        assert_eq!(&lines[6][..15], "| .5. ..6 4.. |");
+        assert_eq!(&lines[7][..15], "| ... ... ... |");
+        assert_eq!(&lines[8][..15], "|             |");
+        assert_eq!(lines[5].len(), lines[0].len());
+    }
+}
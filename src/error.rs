@@ -0,0 +1,116 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::cnf::Variant;
+use crate::Unit;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RudokuError {
+    /// A character in a board string that is not a value on the board
+    InvalidCharacter { character: char, position: usize },
+    /// A character in a puzzle that is neither a value, a blank nor part of
+    /// the grid drawing, counting lines and columns from 1
+    UnexpectedCharacter {
+        character: char,
+        line: usize,
+        column: usize,
+    },
+    /// A whitespace or comma separated token that is not a value on the board
+    InvalidToken { token: String, position: usize },
+    /// A board string with the wrong number of cells
+    InvalidLength { expected: usize, found: usize },
+    /// A value outside of 0..=max passed to `set_value`
+    InvalidValue { value: i32, max: i32 },
+    /// A change to a cell given with the puzzle
+    GivenCell { row: usize, col: usize },
+    /// An error reading one line of a file, counting lines from 1
+    AtLine {
+        line: usize,
+        error: Box<RudokuError>,
+    },
+    /// Box dimensions that do not make a supported board
+    InvalidShape { box_rows: usize, box_cols: usize },
+    /// A cell coordinate outside of the board
+    OutOfBounds { row: usize, col: usize },
+    /// The givens place the same value twice in one unit
+    DuplicateValue { unit: Unit, value: i32 },
+    /// An empty cell has no possible values left
+    Contradiction { row: usize, col: usize },
+    /// No assignment of the empty cells satisfies the board
+    Unsolvable,
+    /// A SAT solver's model gives a cell no value, more than one, or one
+    /// that differs from its given
+    InvalidModel { row: usize, col: usize },
+    /// A solution repeats a value at a cell where a variant forbids it
+    VariantConflict {
+        variant: Variant,
+        row: usize,
+        col: usize,
+    },
+}
+
+impl fmt::Display for RudokuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RudokuError::InvalidCharacter {
+                character,
+                position,
+            } => write!(
+                f,
+                "invalid character {:?} at position {}",
+                character, position
+            ),
+            RudokuError::UnexpectedCharacter {
+                character,
+                line,
+                column,
+            } => write!(
+                f,
+                "unexpected character {:?} at line {}, column {}",
+                character, line, column
+            ),
+            RudokuError::InvalidToken { token, position } => {
+                write!(f, "invalid token {:?} at position {}", token, position)
+            }
+            RudokuError::InvalidLength { expected, found } => {
+                write!(f, "expected {} cells but found {}", expected, found)
+            }
+            RudokuError::InvalidValue { value, max } => {
+                write!(f, "invalid value {}: must be between 0 and {}", value, max)
+            }
+            RudokuError::GivenCell { row, col } => {
+                write!(
+                    f,
+                    "cell ({}, {}) is a given and cannot be changed",
+                    row, col
+                )
+            }
+            RudokuError::AtLine { line, error } => write!(f, "line {}: {}", line, error),
+            RudokuError::InvalidShape { box_rows, box_cols } => {
+                write!(f, "unsupported box shape {}x{}", box_rows, box_cols)
+            }
+            RudokuError::OutOfBounds { row, col } => {
+                write!(f, "cell ({}, {}) is outside of the board", row, col)
+            }
+            RudokuError::DuplicateValue { unit, value } => {
+                write!(f, "{} contains the value {} more than once", unit, value)
+            }
+            RudokuError::Contradiction { row, col } => {
+                write!(f, "cell ({}, {}) has no possible values left", row, col)
+            }
+            RudokuError::Unsolvable => write!(f, "board has no solution"),
+            RudokuError::InvalidModel { row, col } => {
+                write!(
+                    f,
+                    "model does not give cell ({}, {}) a single value",
+                    row, col
+                )
+            }
+            RudokuError::VariantConflict { variant, row, col } => {
+                write!(f, "cell ({}, {}) breaks the {} rule", row, col, variant)
+            }
+        }
+    }
+}
+
+impl Error for RudokuError {}
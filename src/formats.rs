@@ -0,0 +1,474 @@
+//! Reading and writing the file formats of other Sudoku programs:
+//!
+//! - `.sdk`, from SadMan Sudoku: one puzzle as a grid, after optional `#`
+//!   metadata lines
+//! - `.sdm`, also from SadMan Sudoku: one puzzle per line
+//! - `.ss`, from Simple Sudoku: one puzzle as a grid with box borders
+//! - the Sudoku Explainer batch line: a puzzle followed by its ratings
+//!
+//! `read_puzzles` takes text in any of these formats and works out which
+//! one it is.
+
+use std::fmt;
+
+use crate::parse::BORDERS;
+use crate::rating::step_score;
+use crate::{char_value, value_char, Board, DisplayStyle, Rating, RudokuError, Shape, Sudoku};
+
+/// Details about a puzzle. All but `id` and `rating` can be carried by the
+/// `.sdk` format.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct Metadata {
+    pub id: Option<String>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub comment: Option<String>,
+    pub date: Option<String>,
+    pub source: Option<String>,
+    pub level: Option<String>,
+    pub url: Option<String>,
+    pub rating: Option<Rating>,
+}
+
+impl Metadata {
+    /// The `.sdk` code for each field, in the order they are written
+    fn fields(&self) -> [(char, &Option<String>); 7] {
+        [
+            ('A', &self.author),
+            ('D', &self.description),
+            ('C', &self.comment),
+            ('B', &self.date),
+            ('S', &self.source),
+            ('L', &self.level),
+            ('U', &self.url),
+        ]
+    }
+
+    fn field_mut(&mut self, code: char) -> Option<&mut Option<String>> {
+        match code {
+            'A' => Some(&mut self.author),
+            'D' => Some(&mut self.description),
+            'C' => Some(&mut self.comment),
+            'B' => Some(&mut self.date),
+            'S' => Some(&mut self.source),
+            'L' => Some(&mut self.level),
+            'U' => Some(&mut self.url),
+            _ => None,
+        }
+    }
+}
+
+/// A board along with what is known about where it came from
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Puzzle {
+    pub board: Board,
+    pub metadata: Metadata,
+}
+
+impl From<Board> for Puzzle {
+    fn from(board: Board) -> Self {
+        Self {
+            board,
+            metadata: Metadata::default(),
+        }
+    }
+}
+
+/// The three ratings Sudoku Explainer gives a puzzle. Each is the score of
+/// the hardest step needed before some point in the solve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExplainerRating {
+    /// Before the puzzle is solved
+    pub er: f32,
+    /// Before the first value is placed
+    pub ep: f32,
+    /// Before the first step of any kind, which is the first step itself
+    pub ed: f32,
+}
+
+impl ExplainerRating {
+    /// Rate a board from a logical solve of a copy of it
+    pub fn from_board(board: &Board) -> Result<Self, RudokuError> {
+        let steps = board.report()?.steps;
+        let scores = steps.iter().map(step_score).collect::<Vec<f32>>();
+        let first_placement = steps
+            .iter()
+            .position(|step| !step.placements.is_empty())
+            .map_or(steps.len(), |index| index + 1);
+        let hardest = |scores: &[f32]| scores.iter().copied().fold(0.0, f32::max);
+        Ok(Self {
+            er: hardest(&scores),
+            ep: hardest(&scores[..first_placement]),
+            ed: scores.first().copied().unwrap_or(0.0),
+        })
+    }
+}
+
+impl fmt::Display for ExplainerRating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ED={:.1}/{:.1}/{:.1}", self.er, self.ep, self.ed)
+    }
+}
+
+/// The values of a row, `blank` for empty cells, split into boxes
+fn row_boxes(board: &Board, row_index: usize, blank: char) -> Vec<String> {
+    board
+        .get_row(row_index)
+        .into_iter()
+        .map(|value| if value == 0 { blank } else { value_char(value) })
+        .collect::<Vec<char>>()
+        .chunks(board.shape().box_cols())
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+fn line_error(line_index: usize) -> impl Fn(RudokuError) -> RudokuError {
+    move |error| RudokuError::AtLine {
+        line: line_index + 1,
+        error: Box::new(error),
+    }
+}
+
+/// Read an `.sdk` file: `#` lines of metadata, each a letter code and its
+/// text, then the grid
+pub fn read_sdk(text: &str) -> Result<Puzzle, RudokuError> {
+    let mut metadata = Metadata::default();
+    // Metadata lines are blanked rather than dropped so that parse errors
+    // point at the right line
+    let grid = text
+        .lines()
+        .map(|line| {
+            if let Some(field) = line.strip_prefix('#') {
+                let mut chars = field.chars();
+                if let Some(slot) = chars.next().and_then(|code| metadata.field_mut(code)) {
+                    *slot = Some(chars.as_str().trim().to_string());
+                }
+                ""
+            } else if line.starts_with('[') {
+                // Section headers from later versions of the format
+                ""
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<&str>>()
+        .join("\n");
+    Ok(Puzzle {
+        board: grid.parse()?,
+        metadata,
+    })
+}
+
+/// Write a puzzle as an `.sdk` file, with `.` for blanks
+pub fn write_sdk(puzzle: &Puzzle) -> String {
+    let board = &puzzle.board;
+    puzzle
+        .metadata
+        .fields()
+        .iter()
+        .filter_map(|(code, value)| value.as_ref().map(|value| format!("#{}{}\n", code, value)))
+        .chain((0..board.size()).map(|row_index| row_boxes(board, row_index, '.').concat() + "\n"))
+        .collect()
+}
+
+/// Read an `.sdm` file, one puzzle per line. Blank lines and `#` comments
+/// are skipped.
+pub fn read_sdm(text: &str) -> Result<Vec<Board>, RudokuError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(line_index, line)| line.trim().parse().map_err(line_error(line_index)))
+        .collect()
+}
+
+/// Write boards as an `.sdm` file, with `0` for blanks
+pub fn write_sdm(boards: &[Board]) -> String {
+    boards
+        .iter()
+        .map(|board| format!("{}\n", board.display(DisplayStyle::Line)))
+        .collect()
+}
+
+/// Read a Simple Sudoku `.ss` grid, with or without an outer frame
+pub fn read_ss(text: &str) -> Result<Board, RudokuError> {
+    // The outer frame has `*` corners, which would otherwise read as blanks
+    text.lines()
+        .map(|line| if line.starts_with('*') { "" } else { line })
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .parse()
+}
+
+/// Write a board as a Simple Sudoku `.ss` grid, with `.` for blanks
+pub fn write_ss(board: &Board) -> String {
+    let shape = board.shape();
+    let rule = "-".repeat(board.size() + board.size() / shape.box_cols() - 1);
+    (0..board.size())
+        .map(|row_index| {
+            let row = row_boxes(board, row_index, '.').join("|");
+            match row_index > 0 && row_index % shape.box_rows() == 0 {
+                true => format!("{}\n{}\n", rule, row),
+                false => format!("{}\n", row),
+            }
+        })
+        .collect()
+}
+
+/// Read a Sudoku Explainer line: a puzzle, then optionally its ratings as
+/// `ED=er/ep/ed`
+pub fn read_explainer(line: &str) -> Result<(Board, Option<ExplainerRating>), RudokuError> {
+    let mut tokens = line.split_whitespace();
+    let board = tokens.next().unwrap_or("").parse()?;
+    let rating = tokens
+        .enumerate()
+        .find_map(|(position, token)| {
+            token.strip_prefix("ED=").map(|ratings| {
+                let invalid = || RudokuError::InvalidToken {
+                    token: token.to_string(),
+                    position: position + 1,
+                };
+                let scores = ratings
+                    .split('/')
+                    .map(|score| score.parse::<f32>().map_err(|_| invalid()))
+                    .collect::<Result<Vec<f32>, RudokuError>>()?;
+                match scores.as_slice() {
+                    [er, ep, ed] => Ok(ExplainerRating {
+                        er: *er,
+                        ep: *ep,
+                        ed: *ed,
+                    }),
+                    _ => Err(invalid()),
+                }
+            })
+        })
+        .transpose()?;
+    Ok((board, rating))
+}
+
+/// Read puzzles from text in an unknown format. Several lines that each
+/// hold a puzzle, optionally followed by ratings as in `.sdm` files and
+/// Sudoku Explainer output, are read one puzzle per line. Anything else is
+/// read as a single puzzle, with any `.sdk` metadata.
+pub fn read_puzzles(text: &str) -> Result<Vec<Puzzle>, RudokuError> {
+    let lines = text
+        .lines()
+        .enumerate()
+        .map(|(line_index, line)| (line_index, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .collect::<Vec<(usize, &str)>>();
+    if lines.len() > 1 && one_per_line(lines[0].1) {
+        return lines
+            .iter()
+            .map(|(line_index, line)| {
+                let puzzle = line.split_whitespace().next().unwrap_or("");
+                puzzle
+                    .parse::<Board>()
+                    .map(Puzzle::from)
+                    .map_err(line_error(*line_index))
+            })
+            .collect();
+    }
+    read_sdk(text).map(|puzzle| vec![puzzle])
+}
+
+/// Whether text holds a puzzle on each line rather than one grid, judged
+/// from its first line: a puzzle fills a whole board, and a row of a grid
+/// does not. Sixteen cells could be either a 4x4 puzzle or a row of a 16x16
+/// grid, and values past 4 make it a row.
+fn one_per_line(first: &str) -> bool {
+    let cells = first
+        .split_whitespace()
+        .next()
+        .unwrap_or("")
+        .chars()
+        .filter(|c| !BORDERS.contains(c))
+        .collect::<Vec<char>>();
+    let size = match Board::shape_for_cells(cells.len()) {
+        Ok(shape) => shape.size(),
+        Err(_) => return false,
+    };
+    let row_of_a_grid = Shape::for_size(cells.len()).is_some()
+        && cells
+            .iter()
+            .any(|c| char_value(*c, cells.len()).is_some_and(|value| value as usize > size));
+    !row_of_a_grid
+}
+
+/// Write a Sudoku Explainer line, with `.` for blanks
+pub fn write_explainer(board: &Board, rating: Option<&ExplainerRating>) -> String {
+    let puzzle = (0..board.size())
+        .map(|row_index| row_boxes(board, row_index, '.').concat())
+        .collect::<String>();
+    match rating {
+        Some(rating) => format!("{} {}", puzzle, rating),
+        None => puzzle,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str =
+        "379000014060010070080009005435007000090040020000800436900700080040080050850000249";
+
+    fn puzzle() -> Board {
+        Board::from_string(PUZZLE).unwrap()
+    }
+
+    #[test]
+    fn sdk_round_trips_with_metadata() {
+        let puzzle = Puzzle {
+            board: puzzle(),
+            metadata: Metadata {
+                author: Some("Jane Doe".to_string()),
+                level: Some("Easy".to_string()),
+                ..Metadata::default()
+            },
+        };
+        let text = write_sdk(&puzzle);
+        assert!(text.starts_with("#AJane Doe\n#LEasy\n379....14\n.6..1..7.\n"));
+        let read = read_sdk(&text).unwrap();
+        assert_eq!(read.board.rows(), puzzle.board.rows());
+        assert_eq!(read.metadata, puzzle.metadata);
+    }
+
+    #[test]
+    fn sdm_reads_one_puzzle_per_line() {
+        let boards = vec![puzzle(), Board::new()];
+        let text = write_sdm(&boards);
+        assert_eq!(text.lines().next(), Some(PUZZLE));
+        let read = read_sdm(&text).unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[1].rows(), boards[1].rows());
+
+        let text = format!("{}\n\n{}x\n", PUZZLE, &PUZZLE[1..]);
+        assert_eq!(
+            read_sdm(&text).err(),
+            Some(RudokuError::AtLine {
+                line: 3,
+                error: Box::new(RudokuError::UnexpectedCharacter {
+                    character: 'x',
+                    line: 1,
+                    column: 81
+                })
+            })
+        );
+    }
+
+    #[test]
+    fn ss_round_trips() {
+        let text = write_ss(&puzzle());
+        assert_eq!(text.lines().next(), Some("379|...|.14"));
+        assert_eq!(text.lines().nth(3), Some("-----------"));
+        assert_eq!(text.lines().count(), 11);
+        assert_eq!(read_ss(&text).unwrap().rows(), puzzle().rows());
+
+        let framed = "\
+*-----------*
+|379|...|.14|
+|.6.|.1.|.7.|
+|.8.|..9|..5|
+|---+---+---|
+|435|..7|...|
+|.9.|.4.|.2.|
+|...|8..|436|
+|---+---+---|
+|9..|7..|.8.|
+|.4.|.8.|.5.|
+|85.|...|249|
+*-----------*";
+        assert_eq!(read_ss(framed).unwrap().rows(), puzzle().rows());
+    }
+
+    #[test]
+    fn read_puzzles_tells_formats_apart() {
+        let read = read_puzzles(&write_sdm(&[puzzle(), Board::new()])).unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].board.rows(), puzzle().rows());
+
+        let line = write_explainer(
+            &puzzle(),
+            Some(&ExplainerRating::from_board(&puzzle()).unwrap()),
+        );
+        let read = read_puzzles(&format!("{}\n{}\n", line, line)).unwrap();
+        assert_eq!(read.len(), 2);
+
+        let puzzle = Puzzle {
+            board: puzzle(),
+            metadata: Metadata {
+                author: Some("Jane Doe".to_string()),
+                ..Metadata::default()
+            },
+        };
+        let read = read_puzzles(&write_sdk(&puzzle)).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].metadata, puzzle.metadata);
+        assert_eq!(read_puzzles(&write_ss(&puzzle.board)).unwrap().len(), 1);
+
+        let grid = crate::generate::complete_grid(crate::Shape::new(4, 4).unwrap(), 3);
+        let read = read_puzzles(&write_sdk(&Puzzle::from(grid.clone()))).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].board.rows(), grid.rows());
+
+        let text = format!("{}\n{}x\n", PUZZLE, &PUZZLE[1..]);
+        assert!(matches!(
+            read_puzzles(&text),
+            Err(RudokuError::AtLine { line: 2, .. })
+        ));
+        // As many puzzles as each has cells are still read one to a line
+        let read = read_puzzles(&format!("{}\n", PUZZLE).repeat(81)).unwrap();
+        assert_eq!(read.len(), 81);
+        assert!(read
+            .iter()
+            .all(|read| read.board.rows() == puzzle.board.rows()));
+        let small = "1234341221434321\n".repeat(16);
+        assert_eq!(read_puzzles(&small).unwrap().len(), 16);
+
+        // A bad first line is reported like any other
+        let text = format!("{}x\n{}\n{}\n", &PUZZLE[1..], PUZZLE, PUZZLE);
+        assert!(matches!(
+            read_puzzles(&text),
+            Err(RudokuError::AtLine { line: 1, .. })
+        ));
+        let spaced = PUZZLE
+            .chars()
+            .map(String::from)
+            .collect::<Vec<String>>()
+            .chunks(9)
+            .map(|row| row.join(" ") + "\n")
+            .collect::<String>();
+        assert_eq!(
+            read_puzzles(&spaced).unwrap()[0].board.rows(),
+            puzzle.board.rows()
+        );
+    }
+
+    #[test]
+    fn explainer_lines_carry_ratings() {
+        let rating = ExplainerRating::from_board(&puzzle()).unwrap();
+        assert!(rating.ed <= rating.ep && rating.ep <= rating.er);
+        let score = puzzle().rate().unwrap().score;
+        assert!(rating.er <= score && score < rating.er + 0.1);
+        let line = write_explainer(&puzzle(), Some(&rating));
+        assert!(line.starts_with("379....14.6..1..7."));
+
+        let (board, read) = read_explainer(&line).unwrap();
+        assert_eq!(board.rows(), puzzle().rows());
+        assert_eq!(
+            read.map(|rating| rating.to_string()),
+            Some(rating.to_string())
+        );
+
+        let (_, read) = read_explainer(&write_explainer(&puzzle(), None)).unwrap();
+        assert_eq!(read, None);
+        assert!(read_explainer(&format!("{} ED=1.2/x/1.2", PUZZLE)).is_err());
+    }
+}
@@ -0,0 +1,896 @@
+//! Interactive play: a board with the player's notes and a history of every
+//! change, for undo and redo.
+//!
+//! Notes are the player's own, kept apart from the candidates the board
+//! works out, and may well be wrong. Corner notes mark where a value could
+//! go in a unit; centre notes list the values a cell could hold.
+//!
+//! The history is a tree. Undoing a few moves and then playing a different
+//! one starts a new branch, and the old branch stays reachable through
+//! `jump_to`. Redo follows whichever branch was visited last.
+//!
+//! Before corner notes, a game had only pencilmarks, and a move edited one
+//! cell. Pencilmarks are centre notes, and games saved then still load.
+
+use crate::{Board, RudokuError, Sudoku, Unit};
+
+/// The two kinds of note a player can make in a cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum NoteKind {
+    Corner,
+    Centre,
+}
+
+/// What an edit did to its cell, with enough to take it back
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Change {
+    /// The value went from `from` to `to`, 0 for empty
+    Value { from: i32, to: i32 },
+    /// The notes of a kind went from `from` to `to`, in ascending order
+    Notes {
+        kind: NoteKind,
+        from: Vec<i32>,
+        to: Vec<i32>,
+    },
+}
+
+/// A change to one cell
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Edit {
+    pub cell: (usize, usize),
+    pub change: Change,
+}
+
+/// One reversible action of the player. Placing a value also removes it
+/// from the notes of the cell's peers, and filling in notes touches every
+/// empty cell, so a move may edit several cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    pub edits: Vec<Edit>,
+}
+
+/// A point in the history. Entry 0 is the start of the game and has no
+/// move; every other entry is reached from its parent by its move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryEntry {
+    pub parent: Option<usize>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "move", deserialize_with = "saved::read_move")
+    )]
+    pub mv: Option<Move>,
+    // The child that `redo` goes to
+    redo: Option<usize>,
+}
+
+/// A note that the placed values rule out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrongNote {
+    pub cell: (usize, usize),
+    pub kind: NoteKind,
+    pub value: i32,
+}
+
+/// A board in play, with its notes and history
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "GameData", try_from = "GameData")
+)]
+pub struct Game {
+    start: Board,
+    board: Board,
+    // Notes of each cell in reading order, as bitmasks
+    corner: Vec<u32>,
+    centre: Vec<u32>,
+    history: Vec<HistoryEntry>,
+    position: usize,
+}
+
+impl Game {
+    /// Start playing a board. Its filled cells stay as they are, and only
+    /// changes from here on are recorded.
+    pub fn new(board: Board) -> Self {
+        Self {
+            corner: vec![0; board.shape().cell_count()],
+            centre: vec![0; board.shape().cell_count()],
+            start: board.clone(),
+            board,
+            history: vec![HistoryEntry {
+                parent: None,
+                mv: None,
+                redo: None,
+            }],
+            position: 0,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The player's notes of a kind in a cell, in ascending order. A cell
+    /// off the board has none.
+    pub fn notes(&self, row_index: usize, col_index: usize, kind: NoteKind) -> Vec<i32> {
+        match self.in_bounds((row_index, col_index)) {
+            Ok(()) => {
+                crate::mask_values(self.marks(kind)[row_index * self.board.size() + col_index])
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Every point in the history, indexed as for `jump_to`
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+
+    /// The index in `history` of the current point
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Fill in a cell, or clear it with 0. A value is also removed from the
+    /// notes of every cell that shares a unit with this one.
+    pub fn set_value(
+        &mut self,
+        row_index: usize,
+        col_index: usize,
+        value: i32,
+    ) -> Result<(), RudokuError> {
+        let cell = (row_index, col_index);
+        self.in_bounds(cell)?;
+        let change = Change::Value {
+            from: self.board.get_value(row_index, col_index),
+            to: value,
+        };
+        self.check(cell, &change)?;
+        let mut edits = vec![Edit { cell, change }];
+        if value > 0 {
+            for peer in self.peers(cell) {
+                for kind in [NoteKind::Corner, NoteKind::Centre].iter() {
+                    let from = self.notes(peer.0, peer.1, *kind);
+                    let to = from.iter().copied().filter(|note| *note != value).collect();
+                    edits.push(Edit {
+                        cell: peer,
+                        change: Change::Notes {
+                            kind: *kind,
+                            from,
+                            to,
+                        },
+                    });
+                }
+            }
+        }
+        self.play(edits);
+        Ok(())
+    }
+
+    pub fn clear_value(&mut self, row_index: usize, col_index: usize) -> Result<(), RudokuError> {
+        self.set_value(row_index, col_index, 0)
+    }
+
+    /// The centre notes of a cell, which were called pencilmarks before
+    /// there were corner notes
+    pub fn pencilmarks(&self, row_index: usize, col_index: usize) -> Vec<i32> {
+        self.notes(row_index, col_index, NoteKind::Centre)
+    }
+
+    /// Toggle a centre note, as `toggle_note` does
+    pub fn toggle_pencilmark(
+        &mut self,
+        row_index: usize,
+        col_index: usize,
+        value: i32,
+    ) -> Result<(), RudokuError> {
+        self.toggle_note(row_index, col_index, NoteKind::Centre, value)
+    }
+
+    /// Replace the centre notes of a cell, as `set_notes` does
+    pub fn set_pencilmarks(
+        &mut self,
+        row_index: usize,
+        col_index: usize,
+        values: &[i32],
+    ) -> Result<(), RudokuError> {
+        self.set_notes(row_index, col_index, NoteKind::Centre, values)
+    }
+
+    /// Add a note to a cell, or remove it if it is already there
+    pub fn toggle_note(
+        &mut self,
+        row_index: usize,
+        col_index: usize,
+        kind: NoteKind,
+        value: i32,
+    ) -> Result<(), RudokuError> {
+        self.in_bounds((row_index, col_index))?;
+        let mut notes = self.notes(row_index, col_index, kind);
+        match notes.iter().position(|note| *note == value) {
+            Some(index) => {
+                notes.remove(index);
+            }
+            None => notes.push(value),
+        }
+        self.set_notes(row_index, col_index, kind, &notes)
+    }
+
+    /// Replace the notes of a kind in a cell
+    pub fn set_notes(
+        &mut self,
+        row_index: usize,
+        col_index: usize,
+        kind: NoteKind,
+        values: &[i32],
+    ) -> Result<(), RudokuError> {
+        let cell = (row_index, col_index);
+        self.in_bounds(cell)?;
+        let mut to = values.to_vec();
+        to.sort_unstable();
+        to.dedup();
+        let change = Change::Notes {
+            kind,
+            from: self.notes(row_index, col_index, kind),
+            to,
+        };
+        self.check(cell, &change)?;
+        self.play(vec![Edit { cell, change }]);
+        Ok(())
+    }
+
+    /// Set the notes of a kind in every empty cell to the values that the
+    /// placed values leave possible, as one move
+    pub fn fill_notes(&mut self, kind: NoteKind) {
+        let possible = self.board.possible_values();
+        let size = self.board.size();
+        let edits = (0..size * size)
+            .map(|index| (index / size, index % size))
+            .filter(|&(row_index, col_index)| self.board.get_value(row_index, col_index) == 0)
+            .map(|cell| Edit {
+                cell,
+                change: Change::Notes {
+                    kind,
+                    from: self.notes(cell.0, cell.1, kind),
+                    to: possible[&cell].clone(),
+                },
+            })
+            .collect();
+        self.play(edits);
+    }
+
+    /// Notes in empty cells for values that are already placed in one of the
+    /// cell's units, in reading order
+    pub fn wrong_notes(&self) -> Vec<WrongNote> {
+        let possible = self.board.possible_values();
+        let size = self.board.size();
+        let mut wrong = Vec::new();
+        for index in 0..size * size {
+            let cell = (index / size, index % size);
+            if self.board.get_value(cell.0, cell.1) != 0 {
+                continue;
+            }
+            for kind in [NoteKind::Corner, NoteKind::Centre].iter() {
+                self.notes(cell.0, cell.1, *kind)
+                    .into_iter()
+                    .filter(|value| !possible[&cell].contains(value))
+                    .for_each(|value| {
+                        wrong.push(WrongNote {
+                            cell,
+                            kind: *kind,
+                            value,
+                        })
+                    });
+            }
+        }
+        wrong
+    }
+
+    /// Take back the last move. Returns false at the start of the game.
+    pub fn undo(&mut self) -> bool {
+        let entry = &self.history[self.position];
+        match (entry.parent, entry.mv.clone()) {
+            (Some(parent), Some(mv)) => {
+                mv.edits
+                    .iter()
+                    .rev()
+                    .for_each(|edit| self.apply(edit.cell, &edit.change.reversed()));
+                self.position = parent;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Play the move last taken back from here. Returns false if there is
+    /// none.
+    pub fn redo(&mut self) -> bool {
+        match self.history[self.position].redo {
+            Some(child) => {
+                let mv = self.history[child]
+                    .mv
+                    .clone()
+                    .expect("only the start has no move");
+                mv.edits
+                    .iter()
+                    .for_each(|edit| self.apply(edit.cell, &edit.change));
+                self.position = child;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Go to any point in the history, on any branch. Returns false if there
+    /// is no such point.
+    pub fn jump_to(&mut self, target: usize) -> bool {
+        if target >= self.history.len() {
+            return false;
+        }
+        let path = self.path(target);
+        while !path.contains(&self.position) {
+            self.undo();
+        }
+        let from = path
+            .iter()
+            .position(|entry| *entry == self.position)
+            .unwrap_or(0);
+        for pair in path[from..].windows(2) {
+            self.history[pair[0]].redo = Some(pair[1]);
+            self.redo();
+        }
+        true
+    }
+
+    /// The entries from the start of the game to `target`
+    fn path(&self, target: usize) -> Vec<usize> {
+        let mut path = vec![target];
+        while let Some(parent) = self.history[path[path.len() - 1]].parent {
+            path.push(parent);
+        }
+        path.reverse();
+        path
+    }
+
+    /// The other cells that share a unit with a cell
+    fn peers(&self, (row_index, col_index): (usize, usize)) -> Vec<(usize, usize)> {
+        let shape = self.board.shape();
+        let mut peers = Unit::all(shape)
+            .into_iter()
+            .filter(|unit| unit.contains(shape, row_index, col_index))
+            .flat_map(|unit| unit.cells(shape))
+            .filter(|cell| *cell != (row_index, col_index))
+            .collect::<Vec<(usize, usize)>>();
+        peers.sort_unstable();
+        peers.dedup();
+        peers
+    }
+
+    /// Make the edits that change something, and add them after the current
+    /// point, as a new branch if there were moves after it already. A move
+    /// that changes nothing is left out.
+    fn play(&mut self, edits: Vec<Edit>) {
+        let edits = edits
+            .into_iter()
+            .filter(|edit| !edit.change.is_empty())
+            .collect::<Vec<Edit>>();
+        if edits.is_empty() {
+            return;
+        }
+        edits
+            .iter()
+            .for_each(|edit| self.apply(edit.cell, &edit.change));
+        let child = self.history.len();
+        self.history.push(HistoryEntry {
+            parent: Some(self.position),
+            mv: Some(Move { edits }),
+            redo: None,
+        });
+        self.history[self.position].redo = Some(child);
+        self.position = child;
+    }
+
+    fn in_bounds(&self, (row_index, col_index): (usize, usize)) -> Result<(), RudokuError> {
+        let size = self.board.size();
+        match row_index < size && col_index < size {
+            true => Ok(()),
+            false => Err(RudokuError::OutOfBounds {
+                row: row_index,
+                col: col_index,
+            }),
+        }
+    }
+
+    /// Whether a change could be made to the cell, whatever it holds now
+    fn check(
+        &self,
+        (row_index, col_index): (usize, usize),
+        change: &Change,
+    ) -> Result<(), RudokuError> {
+        self.in_bounds((row_index, col_index))?;
+        let size = self.board.size();
+        if self.start.is_given(row_index, col_index) {
+            return Err(RudokuError::GivenCell {
+                row: row_index,
+                col: col_index,
+            });
+        }
+        let max = size as i32;
+        let invalid = match change {
+            Change::Value { from, to } => [*from, *to]
+                .iter()
+                .find(|value| !(0..=max).contains(*value))
+                .copied(),
+            Change::Notes { from, to, .. } => from
+                .iter()
+                .chain(to)
+                .find(|value| !(1..=max).contains(*value))
+                .copied(),
+        };
+        match invalid {
+            Some(value) => Err(RudokuError::InvalidValue { value, max }),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether a cell holds what a change takes it from
+    #[cfg(feature = "serde")]
+    fn starts_from(&self, (row_index, col_index): (usize, usize), change: &Change) -> bool {
+        match change {
+            Change::Value { from, .. } => self.board.get_value(row_index, col_index) == *from,
+            Change::Notes { kind, from, .. } => self.notes(row_index, col_index, *kind) == *from,
+        }
+    }
+
+    fn marks(&self, kind: NoteKind) -> &[u32] {
+        match kind {
+            NoteKind::Corner => &self.corner,
+            NoteKind::Centre => &self.centre,
+        }
+    }
+
+    /// Make a change that has passed `check`
+    fn apply(&mut self, (row_index, col_index): (usize, usize), change: &Change) {
+        let index = row_index * self.board.size() + col_index;
+        match change {
+            Change::Value { to, .. } => self
+                .board
+                .set_value(row_index, col_index, *to)
+                .expect("moves never touch givens"),
+            Change::Notes { kind, to, .. } => {
+                let marks = match kind {
+                    NoteKind::Corner => &mut self.corner,
+                    NoteKind::Centre => &mut self.centre,
+                };
+                marks[index] = to.iter().fold(0, |mask, value| mask | crate::bit(*value));
+            }
+        }
+    }
+}
+
+impl Change {
+    fn reversed(&self) -> Self {
+        match self {
+            Change::Value { from, to } => Change::Value {
+                from: *to,
+                to: *from,
+            },
+            Change::Notes { kind, from, to } => Change::Notes {
+                kind: *kind,
+                from: to.clone(),
+                to: from.clone(),
+            },
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Change::Value { from, to } => from == to,
+            Change::Notes { from, to, .. } => from == to,
+        }
+    }
+}
+
+/// What is saved of a game: the board it started from and the history. The
+/// current board and notes are rebuilt by replaying the moves.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct GameData {
+    start: Board,
+    history: Vec<HistoryEntry>,
+    position: usize,
+}
+
+/// Moves as they were saved before corner notes, when each edited one cell
+/// and pencilmarks were the only notes
+#[cfg(feature = "serde")]
+mod saved {
+    use serde::{Deserialize, Deserializer};
+
+    use super::{Change, Edit, Move, NoteKind};
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum OldChange {
+        Value { from: i32, to: i32 },
+        Pencilmarks { from: Vec<i32>, to: Vec<i32> },
+    }
+
+    #[derive(Deserialize)]
+    struct OldMove {
+        cell: (usize, usize),
+        change: OldChange,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SavedMove {
+        Current(Move),
+        Old(OldMove),
+    }
+
+    impl From<SavedMove> for Move {
+        fn from(saved: SavedMove) -> Self {
+            let old = match saved {
+                SavedMove::Current(mv) => return mv,
+                SavedMove::Old(old) => old,
+            };
+            let change = match old.change {
+                OldChange::Value { from, to } => Change::Value { from, to },
+                OldChange::Pencilmarks { from, to } => Change::Notes {
+                    kind: NoteKind::Centre,
+                    from,
+                    to,
+                },
+            };
+            Move {
+                edits: vec![Edit {
+                    cell: old.cell,
+                    change,
+                }],
+            }
+        }
+    }
+
+    pub(super) fn read_move<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Move>, D::Error> {
+        Option::<SavedMove>::deserialize(deserializer).map(|saved| saved.map(Move::from))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Game> for GameData {
+    fn from(game: Game) -> Self {
+        Self {
+            start: game.start,
+            history: game.history,
+            position: game.position,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<GameData> for Game {
+    type Error = String;
+
+    fn try_from(data: GameData) -> Result<Self, Self::Error> {
+        let mut game = Game::new(data.start);
+        let entries = data.history.len();
+        match data.history.first() {
+            Some(HistoryEntry {
+                parent: None,
+                mv: None,
+                ..
+            }) => {}
+            _ => return Err("history must begin with an entry for the start".to_string()),
+        }
+        if data.position >= entries {
+            return Err(format!(
+                "position {} is past the end of the history",
+                data.position
+            ));
+        }
+        for (index, entry) in data.history.iter().enumerate() {
+            // Parents come first, so following them always reaches the start
+            match (entry.parent, &entry.mv) {
+                (None, None) if index == 0 => {}
+                (Some(parent), Some(mv)) if parent < index => {
+                    // Replay the move from its parent, so that each edit
+                    // starts from what the cell held at that point
+                    game.jump_to(parent);
+                    for edit in &mv.edits {
+                        game.check(edit.cell, &edit.change)
+                            .map_err(|error| format!("move {}: {}", index, error))?;
+                        if !game.starts_from(edit.cell, &edit.change) {
+                            return Err(format!(
+                                "move {} changes r{}c{} from what it did not hold",
+                                index,
+                                edit.cell.0 + 1,
+                                edit.cell.1 + 1
+                            ));
+                        }
+                        game.apply(edit.cell, &edit.change);
+                    }
+                    game.history.push(HistoryEntry {
+                        parent: Some(parent),
+                        mv: Some(mv.clone()),
+                        redo: None,
+                    });
+                    game.position = index;
+                }
+                _ => {
+                    return Err(format!(
+                        "entry {} needs an earlier parent and a move",
+                        index
+                    ))
+                }
+            }
+            if let Some(child) = entry.redo {
+                if data.history.get(child).and_then(|child| child.parent) != Some(index) {
+                    return Err(format!(
+                        "entry {} redoes {}, which is not its child",
+                        index, child
+                    ));
+                }
+            }
+        }
+        let redo = data
+            .history
+            .iter()
+            .map(|entry| entry.redo)
+            .collect::<Vec<Option<usize>>>();
+        game.jump_to(data.position);
+        game.history
+            .iter_mut()
+            .zip(redo)
+            .for_each(|(entry, redo)| entry.redo = redo);
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str =
+        "379000014060010070080009005435007000090040020000800436900700080040080050850000249";
+
+    fn game() -> Game {
+        Game::new(Board::from_string(PUZZLE).unwrap())
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut game = game();
+        assert!(!game.undo());
+        game.set_value(0, 3, 5).unwrap();
+        game.toggle_note(0, 4, NoteKind::Centre, 2).unwrap();
+        game.toggle_note(0, 4, NoteKind::Centre, 6).unwrap();
+        game.clear_value(0, 3).unwrap();
+        assert_eq!(game.history().len(), 5);
+
+        assert!(game.undo());
+        assert_eq!(game.board().get_value(0, 3), 5);
+        assert!(game.undo());
+        assert_eq!(game.notes(0, 4, NoteKind::Centre), [2]);
+        assert!(game.redo());
+        assert_eq!(game.notes(0, 4, NoteKind::Centre), [2, 6]);
+        while game.undo() {}
+        assert_eq!(game.position(), 0);
+        assert_eq!(game.board().rows(), game.start.rows());
+        assert!(game.notes(0, 4, NoteKind::Centre).is_empty());
+        while game.redo() {}
+        assert_eq!(game.position(), 4);
+        assert_eq!(game.board().get_value(0, 3), 0);
+        assert_eq!(game.notes(0, 4, NoteKind::Centre), [2, 6]);
+    }
+
+    #[test]
+    fn refused_and_empty_moves_are_not_recorded() {
+        let mut game = game();
+        assert_eq!(
+            game.set_value(0, 0, 1),
+            Err(RudokuError::GivenCell { row: 0, col: 0 })
+        );
+        assert_eq!(
+            game.toggle_note(0, 0, NoteKind::Corner, 1),
+            Err(RudokuError::GivenCell { row: 0, col: 0 })
+        );
+        assert_eq!(
+            game.toggle_note(0, 3, NoteKind::Corner, 10),
+            Err(RudokuError::InvalidValue { value: 10, max: 9 })
+        );
+        game.clear_value(0, 3).unwrap();
+        game.set_notes(0, 3, NoteKind::Centre, &[]).unwrap();
+        assert_eq!(game.history().len(), 1);
+    }
+
+    #[test]
+    fn cells_off_the_board_are_out_of_bounds() {
+        let mut game = game();
+        let out = Err(RudokuError::OutOfBounds { row: 9, col: 0 });
+        assert_eq!(game.set_value(9, 0, 1), out);
+        assert_eq!(game.clear_value(9, 0), out);
+        assert_eq!(game.toggle_note(9, 0, NoteKind::Corner, 1), out);
+        assert_eq!(game.set_notes(9, 0, NoteKind::Centre, &[1]), out);
+        assert!(game.notes(9, 0, NoteKind::Centre).is_empty());
+        assert_eq!(game.history().len(), 1);
+    }
+
+    #[test]
+    fn branches_stay_reachable() {
+        let mut game = game();
+        game.set_value(0, 3, 5).unwrap();
+        game.set_value(0, 4, 2).unwrap();
+        game.undo();
+        // A new move after an undo starts a second branch from entry 1
+        game.set_value(0, 4, 6).unwrap();
+        assert_eq!(game.history()[3].parent, Some(1));
+        assert!(!game.redo());
+
+        assert!(game.jump_to(2));
+        assert_eq!(game.board().get_value(0, 4), 2);
+        game.undo();
+        game.redo();
+        assert_eq!(game.position(), 2);
+
+        assert!(game.jump_to(3));
+        assert_eq!(game.board().get_value(0, 4), 6);
+        assert!(game.jump_to(0));
+        assert_eq!(game.board().get_value(0, 3), 0);
+        assert!(!game.jump_to(4));
+        game.redo();
+        game.redo();
+        assert_eq!(game.position(), 3);
+    }
+
+    #[test]
+    fn notes_are_filled_from_the_placed_values() {
+        let mut game = game();
+        game.fill_notes(NoteKind::Centre);
+        assert_eq!(game.history().len(), 2);
+        assert_eq!(game.notes(0, 3, NoteKind::Centre), [2, 5, 6]);
+        assert!(game.notes(0, 0, NoteKind::Centre).is_empty());
+        assert!(game.notes(0, 3, NoteKind::Corner).is_empty());
+        assert!(game.wrong_notes().is_empty());
+        game.undo();
+        assert!(game.notes(0, 3, NoteKind::Centre).is_empty());
+    }
+
+    #[test]
+    fn placing_a_value_clears_it_from_peer_notes() {
+        let mut game = game();
+        game.set_notes(0, 4, NoteKind::Centre, &[2, 5, 6]).unwrap();
+        game.set_notes(1, 3, NoteKind::Corner, &[5, 7]).unwrap();
+        game.set_notes(4, 8, NoteKind::Corner, &[5]).unwrap();
+        game.set_value(0, 3, 5).unwrap();
+        assert_eq!(game.notes(0, 4, NoteKind::Centre), [2, 6]);
+        assert_eq!(game.notes(1, 3, NoteKind::Corner), [7]);
+        assert_eq!(game.notes(4, 8, NoteKind::Corner), [5]);
+        let last = game.history().last().unwrap();
+        assert_eq!(last.mv.as_ref().unwrap().edits.len(), 3);
+
+        // One undo puts the notes back as well
+        game.undo();
+        assert_eq!(game.notes(0, 4, NoteKind::Centre), [2, 5, 6]);
+        assert_eq!(game.notes(1, 3, NoteKind::Corner), [5, 7]);
+    }
+
+    #[test]
+    fn pencilmarks_are_centre_notes() {
+        let mut game = game();
+        game.set_pencilmarks(0, 3, &[5, 2]).unwrap();
+        game.toggle_pencilmark(0, 3, 6).unwrap();
+        assert_eq!(game.pencilmarks(0, 3), [2, 5, 6]);
+        assert_eq!(game.notes(0, 3, NoteKind::Centre), [2, 5, 6]);
+    }
+
+    #[test]
+    fn wrong_notes_are_reported() {
+        let mut game = game();
+        game.set_notes(0, 3, NoteKind::Centre, &[1, 2, 5]).unwrap();
+        game.set_notes(0, 4, NoteKind::Corner, &[3, 6]).unwrap();
+        assert_eq!(
+            game.wrong_notes(),
+            [
+                WrongNote {
+                    cell: (0, 3),
+                    kind: NoteKind::Centre,
+                    value: 1
+                },
+                WrongNote {
+                    cell: (0, 4),
+                    kind: NoteKind::Corner,
+                    value: 3
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn history_round_trips() {
+        let mut game = game();
+        game.set_value(0, 3, 5).unwrap();
+        game.toggle_note(0, 4, NoteKind::Corner, 2).unwrap();
+        game.undo();
+        game.set_value(0, 4, 6).unwrap();
+        game.jump_to(2);
+
+        let json = serde_json::to_value(&game).unwrap();
+        assert_eq!(
+            json["history"][1]["move"]["edits"][0],
+            serde_json::json!({"cell": [0, 3], "change": {"value": {"from": 0, "to": 5}}})
+        );
+        assert_eq!(
+            json["history"][2]["move"]["edits"][0]["change"]["notes"]["kind"],
+            "corner"
+        );
+        let mut resumed: Game = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(resumed.position(), 2);
+        assert_eq!(resumed.history(), game.history());
+        assert_eq!(resumed.board().rows(), game.board().rows());
+        assert_eq!(resumed.notes(0, 4, NoteKind::Corner), [2]);
+        resumed.jump_to(3);
+        assert_eq!(resumed.board().get_value(0, 4), 6);
+
+        // The same game saved before corner notes
+        let mut old = json.clone();
+        old["history"][1]["move"] =
+            serde_json::json!({"cell": [0, 3], "change": {"value": {"from": 0, "to": 5}}});
+        old["history"][2]["move"] = serde_json::json!({
+            "cell": [0, 4],
+            "change": {"pencilmarks": {"from": [], "to": [2]}}
+        });
+        old["history"][3]["move"] =
+            serde_json::json!({"cell": [0, 4], "change": {"value": {"from": 0, "to": 6}}});
+        let old: Game = serde_json::from_value(old).unwrap();
+        assert_eq!(old.position(), 2);
+        assert_eq!(old.pencilmarks(0, 4), [2]);
+        assert!(old.notes(0, 4, NoteKind::Corner).is_empty());
+        assert_eq!(old.board().get_value(0, 3), 5);
+
+        let mut broken = json.clone();
+        broken["history"][2]["parent"] = 5.into();
+        assert!(serde_json::from_value::<Game>(broken).is_err());
+        let mut broken = json;
+        broken["history"][1]["move"]["edits"][0]["cell"] = serde_json::json!([0, 0]);
+        assert!(serde_json::from_value::<Game>(broken).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn saves_must_replay() {
+        let mut game = game();
+        game.set_value(0, 3, 5).unwrap();
+        game.set_value(0, 3, 2).unwrap();
+        game.toggle_note(0, 4, NoteKind::Centre, 6).unwrap();
+        let json = serde_json::to_value(&game).unwrap();
+        assert!(serde_json::from_value::<Game>(json.clone()).is_ok());
+
+        // The second move must start from the 5 placed by the first
+        let mut tampered = json.clone();
+        tampered["history"][2]["move"]["edits"][0]["change"]["value"]["from"] = 0.into();
+        let error = serde_json::from_value::<Game>(tampered).unwrap_err();
+        assert!(error.to_string().contains("move 2 changes r1c4"));
+
+        let mut tampered = json;
+        tampered["history"][3]["move"]["edits"][0]["change"]["notes"]["from"] =
+            serde_json::json!([6]);
+        assert!(serde_json::from_value::<Game>(tampered).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{Board, Difficulty, RudokuError, Shape, Sudoku};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    /// 180 degree rotation about the centre cell
+    Rotational,
+    /// Reflection across the middle column
+    Mirror,
+    /// Reflection across the main diagonal
+    Diagonal,
+}
+
+impl Symmetry {
+    /// The cells that must be cleared together with `(row_index, col_index)`
+    /// on a board with `size` rows and columns
+    fn orbit(self, size: usize, row_index: usize, col_index: usize) -> Vec<(usize, usize)> {
+        let mut cells = vec![(row_index, col_index)];
+        let last = size - 1;
+        let partner = match self {
+            Symmetry::None => None,
+            Symmetry::Rotational => Some((last - row_index, last - col_index)),
+            Symmetry::Mirror => Some((row_index, last - col_index)),
+            Symmetry::Diagonal => Some((col_index, row_index)),
+        };
+        if let Some(partner) = partner {
+            if partner != (row_index, col_index) {
+                cells.push(partner);
+            }
+        }
+        cells
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeneratorOptions {
+    pub seed: u64,
+    /// Number of givens to aim for. The generator stops early if no further
+    /// given can be removed without losing a unique solution.
+    pub clues: usize,
+    pub symmetry: Symmetry,
+    pub shape: Shape,
+    /// Band of `Board::rate` to aim for, or `None` to take the first puzzle
+    /// found. Puzzles are carved from fresh grids until one falls in the
+    /// band, for up to `DIFFICULTY_ATTEMPTS` tries.
+    pub difficulty: Option<Difficulty>,
+}
+
+/// Puzzles tried in search of a target difficulty. If none of them falls in
+/// the band, the closest one is used.
+pub const DIFFICULTY_ATTEMPTS: usize = 50;
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            clues: 30,
+            symmetry: Symmetry::None,
+            shape: Shape::default(),
+            difficulty: None,
+        }
+    }
+}
+
+/// Small xorshift generator so that puzzles are reproducible from a seed
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on a zero state, so mix the seed first
+        Self {
+            state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        (1..items.len()).rev().for_each(|i| {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        });
+    }
+}
+
+/// Build a random complete, valid grid
+pub fn complete_grid(shape: Shape, seed: u64) -> Board {
+    let mut board = Board::with_shape(shape);
+    fill(&mut board, &mut Rng::new(seed));
+    board.refresh_candidates();
+    board.mark_givens();
+    board
+}
+
+/// Build a puzzle with a unique solution by removing givens from a random
+/// complete grid. Fails only if a puzzle could not be rated while aiming for
+/// a difficulty.
+pub fn generate(options: &GeneratorOptions) -> Result<Board, RudokuError> {
+    let target = match options.difficulty {
+        None => return Ok(carve(options, options.seed)),
+        Some(target) => target,
+    };
+    // Later attempts take their seeds from the first, so that they do not
+    // repeat the puzzles of neighbouring seeds
+    let mut seeds = Rng::new(options.seed);
+    let mut closest: Option<(u32, Board)> = None;
+    for attempt in 0..DIFFICULTY_ATTEMPTS {
+        let seed = match attempt {
+            0 => options.seed,
+            _ => seeds.next(),
+        };
+        let board = carve(options, seed);
+        let difficulty = board.rate()?.difficulty;
+        let distance = (difficulty as i32 - target as i32).unsigned_abs();
+        if distance == 0 {
+            return Ok(board);
+        }
+        if closest.as_ref().is_none_or(|(best, _)| distance < *best) {
+            closest = Some((distance, board));
+        }
+    }
+    Ok(closest.map_or_else(|| carve(options, options.seed), |(_, board)| board))
+}
+
+/// Remove givens from the complete grid for `seed` down to the clue count
+fn carve(options: &GeneratorOptions, seed: u64) -> Board {
+    let mut rng = Rng::new(seed);
+    let mut board = Board::with_shape(options.shape);
+    fill(&mut board, &mut rng);
+
+    let size = options.shape.size();
+    let mut cells = (0..size)
+        .flat_map(|row_index| (0..size).map(move |col_index| (row_index, col_index)))
+        .collect::<Vec<(usize, usize)>>();
+    rng.shuffle(&mut cells);
+
+    let mut clues = options.shape.cell_count();
+    for (row_index, col_index) in cells {
+        if clues <= options.clues {
+            break;
+        }
+        if board.get_value(row_index, col_index) == 0 {
+            continue;
+        }
+        let orbit = options.symmetry.orbit(size, row_index, col_index);
+        if clues < options.clues + orbit.len() {
+            continue;
+        }
+        let removed = orbit
+            .iter()
+            .map(|&(i, j)| board.get_value(i, j))
+            .collect::<Vec<i32>>();
+        orbit.iter().for_each(|&(i, j)| board.clear_cell(i, j));
+        if board.has_unique_solution() {
+            clues -= orbit.len();
+        } else {
+            orbit
+                .iter()
+                .zip(removed)
+                .for_each(|(&(i, j), value)| board.fill_cell(i, j, value));
+        }
+    }
+    board.refresh_candidates();
+    board.mark_givens();
+    board
+}
+
+fn fill(board: &mut Board, rng: &mut Rng) -> bool {
+    match board.next_cell() {
+        None => board.board_valid(),
+        Some(((row_index, col_index), mask)) => {
+            let mut values = (1..=board.size() as i32)
+                .filter(|value| mask & (1 << (value - 1)) != 0)
+                .collect::<Vec<i32>>();
+            rng.shuffle(&mut values);
+            for value in values {
+                board.fill_cell(row_index, col_index, value);
+                if fill(board, rng) {
+                    return true;
+                }
+                board.clear_cell(row_index, col_index);
+            }
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_string(board: &Board) -> String {
+        board
+            .rows()
+            .iter()
+            .flatten()
+            .map(|value| value.to_string())
+            .collect()
+    }
+
+    fn clue_count(board: &Board) -> usize {
+        board.rows().iter().flatten().filter(|x| **x > 0).count()
+    }
+
+    #[test]
+    fn complete_grid_is_valid_and_complete() {
+        let board = complete_grid(Shape::default(), 7);
+        assert!(board.board_valid());
+        assert!(board.board_complete());
+        assert_eq!(
+            board_string(&board),
+            board_string(&complete_grid(Shape::default(), 7))
+        );
+        assert_ne!(
+            board_string(&board),
+            board_string(&complete_grid(Shape::default(), 8))
+        );
+    }
+
+    #[test]
+    fn generate_is_reproducible_and_unique() {
+        let options = GeneratorOptions {
+            seed: 42,
+            clues: 50,
+            symmetry: Symmetry::None,
+            shape: Shape::default(),
+            difficulty: None,
+        };
+        let board = generate(&options).unwrap();
+        assert_eq!(clue_count(&board), 50);
+        assert!(board.has_unique_solution());
+        assert_eq!(
+            board_string(&board),
+            board_string(&generate(&options).unwrap())
+        );
+
+        let parsed = Board::from_string(&board_string(&board)).unwrap();
+        assert_eq!(parsed.rows(), board.rows());
+    }
+
+    #[test]
+    fn generate_respects_symmetry() {
+        let symmetries = [Symmetry::Rotational, Symmetry::Mirror, Symmetry::Diagonal];
+        symmetries.iter().for_each(|&symmetry| {
+            let board = generate(&GeneratorOptions {
+                seed: 3,
+                clues: 54,
+                symmetry,
+                shape: Shape::default(),
+                difficulty: None,
+            })
+            .unwrap();
+            assert!(board.has_unique_solution());
+            (0..9).for_each(|i| {
+                (0..9).for_each(|j| {
+                    symmetry.orbit(9, i, j).iter().for_each(|&(k, l)| {
+                        assert_eq!(board.get_value(i, j) == 0, board.get_value(k, l) == 0);
+                    });
+                });
+            });
+        });
+    }
+
+    #[test]
+    fn generate_other_sizes() {
+        let board = generate(&GeneratorOptions {
+            seed: 5,
+            clues: 14,
+            symmetry: Symmetry::Rotational,
+            shape: Shape::new(2, 3).unwrap(),
+            difficulty: None,
+        })
+        .unwrap();
+        assert_eq!(board.size(), 6);
+        assert!(board.has_unique_solution());
+
+        let board = complete_grid(Shape::new(4, 4).unwrap(), 11);
+        assert!(board.board_valid());
+        assert!(board.board_complete());
+    }
+
+    #[test]
+    fn generate_aims_for_a_difficulty() {
+        [Difficulty::Easy, Difficulty::Hard]
+            .iter()
+            .for_each(|&target| {
+                let options = GeneratorOptions {
+                    seed: 9,
+                    clues: 24,
+                    difficulty: Some(target),
+                    ..GeneratorOptions::default()
+                };
+                let board = generate(&options).unwrap();
+                assert!(board.has_unique_solution());
+                assert_eq!(board.rate().unwrap().difficulty, target);
+                assert_eq!(
+                    board_string(&board),
+                    board_string(&generate(&options).unwrap())
+                );
+            });
+    }
+}
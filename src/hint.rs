@@ -0,0 +1,137 @@
+use std::fmt;
+
+use crate::{Shape, SolveStep, TechniqueKind, Unit};
+
+/// How much of the next step a hint gives away, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum HintLevel {
+    /// The row, column or square to look at
+    Region,
+    /// The region and the technique to use there
+    Technique,
+    /// The placements and eliminations, with an explanation
+    Step,
+}
+
+/// The next step of a solve, given away up to a `HintLevel`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hint {
+    pub level: HintLevel,
+    pub region: Unit,
+    /// Only from `HintLevel::Technique` on
+    pub technique: Option<TechniqueKind>,
+    /// Only at `HintLevel::Step`
+    pub step: Option<SolveStep>,
+}
+
+impl Hint {
+    pub(crate) fn new(shape: Shape, step: SolveStep, level: HintLevel) -> Self {
+        // Steps without units, such as guesses and wings, point to the square
+        // of their first cell
+        let region = step.units.first().copied().unwrap_or_else(|| {
+            let (row_index, col_index) = step
+                .placements
+                .iter()
+                .map(|(cell, _)| *cell)
+                .chain(step.cells.iter().copied())
+                .next()
+                .unwrap_or((0, 0));
+            Unit::square_of(shape, row_index, col_index)
+        });
+        Self {
+            level,
+            region,
+            technique: Some(step.technique).filter(|_| level >= HintLevel::Technique),
+            step: Some(step).filter(|_| level == HintLevel::Step),
+        }
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.technique, &self.step) {
+            (_, Some(step)) => write!(f, "{}", step),
+            (Some(TechniqueKind::Guess), None) => write!(
+                f,
+                "Look at {}: the techniques run out, so try a value from the solution",
+                self.region
+            ),
+            (Some(technique), None) => write!(f, "Look at {}: {}", self.region, technique),
+            (None, None) => write!(f, "Look at {}", self.region),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, RudokuError, Sudoku};
+
+    const PUZZLE: &str =
+        "379000014060010070080009005435007000090040020000800436900700080040080050850000249";
+
+    #[test]
+    fn hints_give_away_more_at_each_level() {
+        let board = Board::from_string(PUZZLE).unwrap();
+        let region = board.hint(HintLevel::Region).unwrap().unwrap();
+        let technique = board.hint(HintLevel::Technique).unwrap().unwrap();
+        let step = board.hint(HintLevel::Step).unwrap().unwrap();
+
+        assert_eq!(region.technique, None);
+        assert_eq!(region.step, None);
+        assert_eq!(technique.technique, Some(TechniqueKind::NakedSingle));
+        assert_eq!(technique.step, None);
+        assert_eq!(step.step, board.find_step());
+        assert!(region.region == technique.region && technique.region == step.region);
+
+        let unit = step.step.as_ref().unwrap().units[0];
+        assert_eq!(region.to_string(), format!("Look at {}", unit));
+        assert_eq!(
+            technique.to_string(),
+            format!("Look at {}: Naked single", unit)
+        );
+        assert_eq!(step.to_string(), board.find_step().unwrap().to_string());
+    }
+
+    #[test]
+    fn hints_follow_the_player() {
+        let mut board = Board::from_string(PUZZLE).unwrap();
+        let first = board.hint(HintLevel::Step).unwrap().unwrap().step.unwrap();
+        let ((row_index, col_index), value) = first.placements[0];
+        board.set_value(row_index, col_index, value).unwrap();
+        let next = board.hint(HintLevel::Step).unwrap().unwrap().step.unwrap();
+        assert_ne!(next.placements, first.placements);
+
+        // A wrong value leaves nothing to hint at
+        let mut solution = board.clone();
+        solution.solve().unwrap();
+        let (row_index, col_index) = next.placements[0].0;
+        let wrong = solution.get_value(row_index, col_index) % 9 + 1;
+        board.set_value(row_index, col_index, wrong).unwrap();
+        assert!(board.hint(HintLevel::Region).is_err());
+
+        assert_eq!(solution.hint(HintLevel::Step), Ok(None));
+        let clash = Board::from_string(&PUZZLE.replacen('0', "3", 1)).unwrap();
+        assert!(matches!(
+            clash.hint(HintLevel::Step),
+            Err(RudokuError::DuplicateValue { .. })
+        ));
+    }
+
+    #[test]
+    fn hints_fall_back_to_the_solution() {
+        let hint = Board::new().hint(HintLevel::Technique).unwrap().unwrap();
+        assert_eq!(hint.technique, Some(TechniqueKind::Guess));
+        assert_eq!(hint.region, Unit::Square(0));
+        assert_eq!(
+            hint.to_string(),
+            "Look at square 1: the techniques run out, so try a value from the solution"
+        );
+    }
+}
@@ -1,69 +1,770 @@
 use std::collections::HashMap;
 use std::fmt;
 
-const COMPLETED_SEGMENT_SIZE: i32 = 1 + 2 + 3 + 4 + 5 + 6 + 7 + 8 + 9;
-const WIDTH: usize = 9;
-const HEIGHT: usize = 9;
-const SQUARE_SIZE: usize = 3;
-
-fn segment_valid(segment: &Vec<i32>) -> bool {
-    let segment_copy = segment.clone();
-    let segment_no_zeroes = segment_copy
+pub mod batch;
+pub mod cnf;
+mod display;
+mod error;
+pub mod formats;
+pub mod game;
+pub mod generate;
+mod hint;
+mod parse;
+pub mod rating;
+#[cfg(feature = "serde")]
+mod schema;
+mod shape;
+pub mod solver;
+pub mod techniques;
+
+pub use display::{BoardDisplay, DisplayStyle};
+pub use error::RudokuError;
+pub use hint::{Hint, HintLevel};
+pub use parse::looks_like_puzzle;
+pub use rating::{Difficulty, Rating, SolveReport};
+pub use shape::Shape;
+pub use techniques::{SolveStep, Technique, TechniqueKind};
+
+/// Each value that appears more than once in a segment, with the positions
+/// it appears at
+fn segment_conflicts(segment: &[i32]) -> Vec<(i32, Vec<usize>)> {
+    let mut values = segment
         .iter()
-        .filter(|x| (**x) > 0)
-        .collect::<Vec<&i32>>();
-    let mut segment_no_zeroes_dedup = segment_no_zeroes.clone();
-    segment_no_zeroes_dedup.sort();
-    segment_no_zeroes_dedup.dedup();
-    segment_no_zeroes.len() == segment_no_zeroes_dedup.len()
+        .copied()
+        .filter(|x| *x > 0)
+        .collect::<Vec<i32>>();
+    values.sort();
+    values.dedup();
+    values
+        .into_iter()
+        .map(|value| {
+            let positions = (0..segment.len())
+                .filter(|position| segment[*position] == value)
+                .collect::<Vec<usize>>();
+            (value, positions)
+        })
+        .filter(|(_, positions)| positions.len() > 1)
+        .collect()
 }
 
-fn segments_valid(segments: Vec<Vec<i32>>) -> bool {
-    segments
-        .iter()
-        .map(|segment| segment_valid(segment))
-        .all(|segment| segment == true)
+#[cfg(test)]
+fn segment_valid(segment: &[i32]) -> bool {
+    segment_conflicts(segment).is_empty()
 }
 
-fn segment_complete(segment: &Vec<i32>) -> bool {
-    segment.iter().sum::<i32>() == COMPLETED_SEGMENT_SIZE
+#[cfg(test)]
+fn segment_complete(segment: &[i32]) -> bool {
+    // A unit of size n holds each of 1..=n exactly once
+    let mut values = segment.to_vec();
+    values.sort();
+    values.iter().copied().eq(1..=segment.len() as i32)
 }
 
-fn segments_complete(segments: Vec<Vec<i32>>) -> bool {
-    segments
-        .iter()
-        .map(|segment| segment_complete(segment))
-        .all(|segment| segment == true)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Unit {
+    Row(usize),
+    Col(usize),
+    Square(usize),
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unit::Row(index) => write!(f, "row {}", index + 1),
+            Unit::Col(index) => write!(f, "column {}", index + 1),
+            Unit::Square(index) => write!(f, "square {}", index + 1),
+        }
+    }
+}
+
+impl Unit {
+    /// Every row, column and square on a board of the given shape
+    pub fn all(shape: Shape) -> Vec<Unit> {
+        (0..shape.size())
+            .map(Unit::Row)
+            .chain((0..shape.size()).map(Unit::Col))
+            .chain((0..shape.size()).map(Unit::Square))
+            .collect()
+    }
+
+    /// The coordinates of the cells in the unit, in reading order
+    pub fn cells(self, shape: Shape) -> Vec<(usize, usize)> {
+        match self {
+            Unit::Row(row_index) => (0..shape.size()).map(|j| (row_index, j)).collect(),
+            Unit::Col(col_index) => (0..shape.size()).map(|i| (i, col_index)).collect(),
+            Unit::Square(index) => {
+                let across = shape.size() / shape.box_cols();
+                let top = (index / across) * shape.box_rows();
+                let left = (index % across) * shape.box_cols();
+                (0..shape.size())
+                    .map(|k| (top + k / shape.box_cols(), left + k % shape.box_cols()))
+                    .collect()
+            }
+        }
+    }
+
+    /// The square containing a cell
+    pub fn square_of(shape: Shape, row_index: usize, col_index: usize) -> Unit {
+        Unit::Square(shape.square_index(row_index, col_index))
+    }
+
+    /// Whether a cell lies in the unit
+    pub fn contains(self, shape: Shape, row_index: usize, col_index: usize) -> bool {
+        match self {
+            Unit::Row(index) => row_index == index,
+            Unit::Col(index) => col_index == index,
+            Unit::Square(index) => shape.square_index(row_index, col_index) == index,
+        }
+    }
+}
+
+/// A value placed more than once in a unit
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conflict {
+    pub unit: Unit,
+    pub value: i32,
+    /// Every cell in the unit holding the value, in reading order
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} has {} in {}",
+            self.unit,
+            self.value,
+            techniques::join_cells(&self.cells)
+        )
+    }
+}
+
+fn bit(value: i32) -> u32 {
+    1 << (value - 1)
+}
+
+/// A mask with the bits of every value on a board of the given size
+fn all_values(size: usize) -> u32 {
+    u32::MAX >> (32 - size)
+}
+
+/// The values whose bits are set in a mask, in ascending order
+fn mask_values(mask: u32) -> Vec<i32> {
+    (1..=32).filter(|value| mask & bit(*value) != 0).collect()
+}
+
+/// The character for a value: digits up to 9, then letters from A for 10
+fn value_char(value: i32) -> char {
+    std::char::from_digit(value as u32, 36).map_or('?', |c| c.to_ascii_uppercase())
+}
+
+/// Parse a single character, accepting letters from A for 10
+fn char_value(character: char, size: usize) -> Option<u8> {
+    character
+        .to_digit(36)
+        .filter(|value| *value as usize <= size)
+        .map(|value| value as u8)
 }
 
+/// Parse a whitespace or comma separated token: a number, or a single letter
+fn token_value(token: &str, size: usize) -> Option<u8> {
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(character), None) => char_value(character, size),
+        _ => token
+            .parse::<usize>()
+            .ok()
+            .filter(|value| *value <= size)
+            .map(|value| value as u8),
+    }
+}
+
+/// A puzzle and its progress, on a board of any shape.
+///
+/// The grid used to be the public field `board: Vec<Vec<i32>>`. It is now
+/// stored flat with its unit masks kept alongside, so read it with
+/// `Sudoku::rows` and change it with `set_value` and `clear_value`.
+#[derive(Debug, Clone)]
 pub struct Board {
-    pub board: Vec<Vec<i32>>,
-    // pub possible_values: HashMap<(usize, usize), Vec<i32>>,
+    shape: Shape,
+    // Flat grid in reading order, 0 for an empty cell
+    cells: Vec<u8>,
+    // Values placed in each row, column and square, as bitmasks
+    row_masks: Vec<u32>,
+    col_masks: Vec<u32>,
+    square_masks: Vec<u32>,
+    // Candidates that survive the eliminations made so far, as bitmasks.
+    // Kept in step with `set_value`.
+    candidates: Vec<u32>,
+    // Cells filled in when the puzzle was set, as opposed to by solving or
+    // by the player. `set_value` leaves these alone.
+    givens: Vec<bool>,
 }
 
 impl Board {
+    /// An empty 9x9 board
     pub fn new() -> Self {
-        // let mut possible_values = HashMap::new();
-        // (0..HEIGHT).for_each(|i| {
-        //     (0..WIDTH).for_each(|j| {
-        //         possible_values.insert((i, j), ALL_POSSIBLE_VALUES);
-        //     });
-        // });
+        Self::with_shape(Shape::default())
+    }
+
+    /// An empty board with boxes of the given shape
+    pub fn with_shape(shape: Shape) -> Self {
+        let size = shape.size();
         Self {
-            board: vec![vec![0; WIDTH]; HEIGHT],
-            // possible_values: possible_values,
+            shape,
+            cells: vec![0; shape.cell_count()],
+            row_masks: vec![0; size],
+            col_masks: vec![0; size],
+            square_masks: vec![0; size],
+            candidates: vec![all_values(size); shape.cell_count()],
+            givens: vec![false; shape.cell_count()],
         }
     }
-    pub fn from_string(board_string: &str) -> Self {
-        let board = board_string
-            .chars()
-            .map(|character| character.to_digit(10).unwrap() as i32)
-            .collect::<Vec<i32>>()
-            .chunks(WIDTH)
-            .map(|chunk| chunk.to_vec())
-            .collect();
-        // let possible_values =
-        Self { board: board }
+
+    /// Parse a board, taking its shape from the number of cells. Cells are
+    /// either single characters, with letters from A standing for 10 and up,
+    /// or tokens separated by whitespace or commas. 0 is an empty cell.
+    pub fn from_string(board_string: &str) -> Result<Self, RudokuError> {
+        let found = match Self::tokens(board_string) {
+            Tokens::Chars(chars) => chars.len(),
+            Tokens::Words(words) => words.len(),
+        };
+        Self::from_string_with_shape(board_string, Self::shape_for_cells(found)?)
+    }
+
+    /// The usual shape for a board with `found` cells
+    fn shape_for_cells(found: usize) -> Result<Shape, RudokuError> {
+        let size = (1..=found).find(|size| size * size >= found).unwrap_or(0);
+        Some(size)
+            .filter(|size| size * size == found)
+            .and_then(Shape::for_size)
+            .ok_or(RudokuError::InvalidLength {
+                expected: Shape::default().cell_count(),
+                found,
+            })
+    }
+
+    /// A board holding `values` in reading order, 0 for blanks
+    fn from_values(shape: Shape, values: Vec<u8>) -> Result<Self, RudokuError> {
+        if values.len() != shape.cell_count() {
+            return Err(RudokuError::InvalidLength {
+                expected: shape.cell_count(),
+                found: values.len(),
+            });
+        }
+        let mut board = Self::with_shape(shape);
+        board.cells = values;
+        board.rebuild_masks();
+        board.refresh_candidates();
+        board.mark_givens();
+        Ok(board)
+    }
+
+    /// Treat every filled cell as a given
+    pub(crate) fn mark_givens(&mut self) {
+        self.givens = self.cells.iter().map(|value| *value > 0).collect();
+    }
+
+    /// Parse a board with boxes of the given shape, for shapes that can't be
+    /// told from the number of cells, such as 3x2 boxes
+    pub fn from_string_with_shape(board_string: &str, shape: Shape) -> Result<Self, RudokuError> {
+        let size = shape.size();
+        let values = match Self::tokens(board_string) {
+            Tokens::Chars(chars) => chars
+                .into_iter()
+                .enumerate()
+                .map(|(position, character)| {
+                    char_value(character, size).ok_or(RudokuError::InvalidCharacter {
+                        character,
+                        position,
+                    })
+                })
+                .collect::<Result<Vec<u8>, RudokuError>>()?,
+            Tokens::Words(words) => words
+                .into_iter()
+                .enumerate()
+                .map(|(position, token)| {
+                    token_value(token, size).ok_or_else(|| RudokuError::InvalidToken {
+                        token: token.to_string(),
+                        position,
+                    })
+                })
+                .collect::<Result<Vec<u8>, RudokuError>>()?,
+        };
+        Self::from_values(shape, values)
+    }
+
+    fn tokens(board_string: &str) -> Tokens<'_> {
+        let separator = |c: char| c.is_whitespace() || c == ',';
+        if board_string.contains(separator) {
+            Tokens::Words(
+                board_string
+                    .split(separator)
+                    .filter(|token| !token.is_empty())
+                    .collect(),
+            )
+        } else {
+            Tokens::Chars(board_string.chars().collect())
+        }
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    /// The number of rows, columns and squares, and the largest value
+    pub fn size(&self) -> usize {
+        self.shape.size()
+    }
+
+    fn index(&self, row_index: usize, col_index: usize) -> usize {
+        row_index * self.size() + col_index
+    }
+
+    fn cell(&self, index: usize) -> (usize, usize) {
+        (index / self.size(), index % self.size())
+    }
+
+    /// Whether every cell is filled in without breaking any unit
+    pub fn is_solved(&self) -> bool {
+        self.board_valid() && self.board_complete()
+    }
+
+    /// The value in a cell, 0 if it is empty
+    pub fn get_value(&self, row_index: usize, col_index: usize) -> i32 {
+        self.cells[self.index(row_index, col_index)] as i32
+    }
+
+    /// Whether a cell was filled in when the puzzle was set
+    pub fn is_given(&self, row_index: usize, col_index: usize) -> bool {
+        self.givens[self.index(row_index, col_index)]
+    }
+
+    /// The values given with the puzzle as rows, 0 for every other cell
+    pub fn givens(&self) -> Vec<Vec<i32>> {
+        self.cells
+            .chunks(self.size())
+            .zip(self.givens.chunks(self.size()))
+            .map(|(row, givens)| {
+                row.iter()
+                    .zip(givens)
+                    .map(|(value, given)| if *given { *value as i32 } else { 0 })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Clear every cell that is not a given, along with any eliminations
+    pub fn reset_to_givens(&mut self) {
+        for (value, given) in self.cells.iter_mut().zip(&self.givens) {
+            if !given {
+                *value = 0;
+            }
+        }
+        self.rebuild_masks();
+        self.refresh_candidates();
+    }
+
+    /// The candidate grid, including any eliminations made by techniques
+    pub fn candidates(&self) -> HashMap<(usize, usize), Vec<i32>> {
+        (0..self.cells.len())
+            .map(|index| (self.cell(index), mask_values(self.candidates[index])))
+            .collect()
+    }
+
+    /// The candidates left for a single cell
+    pub fn cell_candidates(&self, row_index: usize, col_index: usize) -> Vec<i32> {
+        mask_values(self.candidates[self.index(row_index, col_index)])
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_candidates(&mut self, cell: (usize, usize), values: &[i32]) {
+        let index = self.index(cell.0, cell.1);
+        self.candidates[index] = values.iter().fold(0, |mask, value| mask | bit(*value));
+    }
+
+    pub(crate) fn remove_candidate(&mut self, cell: (usize, usize), value: i32) {
+        let index = self.index(cell.0, cell.1);
+        self.candidates[index] &= !bit(value);
+    }
+
+    /// Rebuild the candidate grid from the placed values, dropping any
+    /// eliminations made by techniques
+    pub fn refresh_candidates(&mut self) {
+        for index in 0..self.cells.len() {
+            let (row_index, col_index) = self.cell(index);
+            self.candidates[index] = match self.cells[index] {
+                0 => self.free_mask(row_index, col_index),
+                value => bit(value as i32),
+            }
+        }
+    }
+
+    fn rebuild_masks(&mut self) {
+        self.row_masks.iter_mut().for_each(|mask| *mask = 0);
+        self.col_masks.iter_mut().for_each(|mask| *mask = 0);
+        self.square_masks.iter_mut().for_each(|mask| *mask = 0);
+        for index in 0..self.cells.len() {
+            if self.cells[index] > 0 {
+                let (row_index, col_index) = self.cell(index);
+                let mask = bit(self.cells[index] as i32);
+                self.row_masks[row_index] |= mask;
+                self.col_masks[col_index] |= mask;
+                self.square_masks[self.shape.square_index(row_index, col_index)] |= mask;
+            }
+        }
+    }
+
+    /// Number of cells holding a value
+    fn filled(&self) -> usize {
+        self.cells.iter().filter(|value| **value != 0).count()
+    }
+
+    /// Whether no unit of a kind holds a value twice. A unit's mask has a
+    /// bit for each distinct value in it, so across all units of a kind the
+    /// bits add up to the filled cells only when there are no duplicates.
+    fn masks_valid(&self, masks: &[u32], filled: usize) -> bool {
+        masks
+            .iter()
+            .map(|mask| mask.count_ones() as usize)
+            .sum::<usize>()
+            == filled
+    }
+
+    /// Whether every unit of a kind holds all of its values, and so is full
+    /// with no duplicates
+    fn masks_complete(&self, masks: &[u32]) -> bool {
+        masks.iter().all(|mask| *mask == all_values(self.size()))
+    }
+
+    /// Values not yet placed in the row, column or square of a cell
+    fn free_mask(&self, row_index: usize, col_index: usize) -> u32 {
+        !(self.row_masks[row_index]
+            | self.col_masks[col_index]
+            | self.square_masks[self.shape.square_index(row_index, col_index)])
+            & all_values(self.size())
+    }
+
+    /// Write a value into an empty cell and its unit masks, leaving the
+    /// candidates alone
+    pub(crate) fn fill_cell(&mut self, row_index: usize, col_index: usize, value: i32) {
+        let mask = bit(value);
+        let index = self.index(row_index, col_index);
+        self.cells[index] = value as u8;
+        self.row_masks[row_index] |= mask;
+        self.col_masks[col_index] |= mask;
+        self.square_masks[self.shape.square_index(row_index, col_index)] |= mask;
+    }
+
+    /// Undo `fill_cell`. Only exact on a board without duplicates.
+    pub(crate) fn clear_cell(&mut self, row_index: usize, col_index: usize) {
+        let index = self.index(row_index, col_index);
+        if self.cells[index] == 0 {
+            return;
+        }
+        let mask = !bit(self.cells[index] as i32);
+        self.cells[index] = 0;
+        self.row_masks[row_index] &= mask;
+        self.col_masks[col_index] &= mask;
+        self.square_masks[self.shape.square_index(row_index, col_index)] &= mask;
+    }
+
+    /// Fill a cell and remove the value from the candidates of its peers
+    fn place(&mut self, row_index: usize, col_index: usize, value: i32) {
+        self.fill_cell(row_index, col_index, value);
+        let mask = !bit(value);
+        let size = self.size();
+        (0..size).for_each(|j| self.candidates[row_index * size + j] &= mask);
+        (0..size).for_each(|i| self.candidates[i * size + col_index] &= mask);
+        Unit::square_of(self.shape, row_index, col_index)
+            .cells(self.shape)
+            .into_iter()
+            .for_each(|(i, j)| self.candidates[i * size + j] &= mask);
+        let index = self.index(row_index, col_index);
+        self.candidates[index] = bit(value);
+    }
+
+    /// The first step found by the default techniques, simplest first
+    pub fn find_step(&self) -> Option<SolveStep> {
+        self.find_step_with(&techniques::default_techniques())
+    }
+
+    /// Apply a step's placements and eliminations to the board
+    pub fn apply(&mut self, step: &SolveStep) {
+        step.placements
+            .iter()
+            .for_each(|&((row_index, col_index), value)| self.place(row_index, col_index, value));
+        step.eliminations
+            .iter()
+            .for_each(|(cell, value)| self.remove_candidate(*cell, *value));
+    }
+
+    /// Find and apply the simplest available step
+    pub fn solve_step(&mut self) -> Option<SolveStep> {
+        let step = self.find_step()?;
+        self.apply(&step);
+        Some(step)
+    }
+
+    /// The first step found by `techniques`, in the order given
+    fn find_step_with(&self, techniques: &[Box<dyn Technique>]) -> Option<SolveStep> {
+        techniques.iter().find_map(|technique| technique.find(self))
+    }
+
+    /// Rate the board from a logical solve of a copy of it
+    pub fn rate(&self) -> Result<Rating, RudokuError> {
+        self.report().map(|report| report.rating)
+    }
+
+    /// Solve a copy of the board, keeping the steps taken and their rating
+    pub fn report(&self) -> Result<SolveReport, RudokuError> {
+        let mut solution = self.clone();
+        let steps = solution.trace_with(&rating::rating_techniques())?;
+        let rating = Rating::from_steps(&steps);
+        Ok(SolveReport {
+            solution,
+            steps,
+            rating,
+        })
+    }
+
+    /// Solve the board one step at a time, returning every step taken. When
+    /// the techniques run out, the empty cell with the fewest candidates is
+    /// filled in from the solution as a `Guess` step.
+    pub fn solve_trace(&mut self) -> Result<Vec<SolveStep>, RudokuError> {
+        self.trace_with(&techniques::default_techniques())
+    }
+
+    /// `solve_trace`, trying `techniques` in the order given
+    pub(crate) fn trace_with(
+        &mut self,
+        techniques: &[Box<dyn Technique>],
+    ) -> Result<Vec<SolveStep>, RudokuError> {
+        if let Some(error) = self.duplicate() {
+            return Err(error);
+        }
+        let mut steps = Vec::new();
+        let mut solution: Option<Board> = None;
+        while !self.board_complete() {
+            if !self.board_valid() {
+                return Err(RudokuError::Unsolvable);
+            }
+            if let Some(error) = self.contradiction() {
+                return Err(error);
+            }
+            if let Some(step) = self.find_step_with(techniques) {
+                self.apply(&step);
+                steps.push(step);
+                continue;
+            }
+            if solution.is_none() {
+                let mut solved = self.clone();
+                if !solved.search() {
+                    return Err(RudokuError::Unsolvable);
+                }
+                solution = Some(solved);
+            }
+            let step = solution
+                .as_ref()
+                .and_then(|solved| self.guess(solved))
+                .ok_or(RudokuError::Unsolvable)?;
+            self.apply(&step);
+            steps.push(step);
+        }
+        Ok(steps)
+    }
+
+    /// A `Guess` step filling in the empty cell with the fewest candidates
+    /// from the solution
+    fn guess(&self, solution: &Board) -> Option<SolveStep> {
+        let index = (0..self.cells.len())
+            .filter(|index| self.cells[*index] == 0)
+            .min_by_key(|index| (self.candidates[*index].count_ones(), *index))?;
+        Some(SolveStep::placement(
+            TechniqueKind::Guess,
+            self.cell(index),
+            solution.cells[index] as i32,
+        ))
+    }
+
+    /// A hint towards the simplest next step, giving away as much as `level`
+    /// allows, or `None` once the board is full. Any state without conflicts
+    /// will do, including one the player has filled in part of; when the
+    /// techniques run out, the hint is a value from the solution.
+    pub fn hint(&self, level: HintLevel) -> Result<Option<Hint>, RudokuError> {
+        if let Some(error) = self.duplicate() {
+            return Err(error);
+        }
+        if self.board_complete() {
+            return Ok(None);
+        }
+        if let Some(error) = self.contradiction() {
+            return Err(error);
+        }
+        // A wrong value from the player leaves no solution, and no step
+        // found from there would help
+        let mut solution = self.clone();
+        if !solution.search() {
+            return Err(RudokuError::Unsolvable);
+        }
+        let step = self
+            .find_step()
+            .or_else(|| self.guess(&solution))
+            .ok_or(RudokuError::Unsolvable)?;
+        Ok(Some(Hint::new(self.shape, step, level)))
+    }
+
+    /// Every value placed more than once in a row, column or square. A cell
+    /// can appear in up to three conflicts, one for each of its units.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        Unit::all(self.shape)
+            .into_iter()
+            .flat_map(|unit| {
+                let cells = unit.cells(self.shape);
+                let segment = cells
+                    .iter()
+                    .map(|&(i, j)| self.get_value(i, j))
+                    .collect::<Vec<i32>>();
+                segment_conflicts(&segment)
+                    .into_iter()
+                    .map(move |(value, positions)| Conflict {
+                        unit,
+                        value,
+                        cells: positions.into_iter().map(|k| cells[k]).collect(),
+                    })
+                    .collect::<Vec<Conflict>>()
+            })
+            .collect()
+    }
+
+    fn duplicate(&self) -> Option<RudokuError> {
+        self.conflicts()
+            .into_iter()
+            .next()
+            .map(|conflict| RudokuError::DuplicateValue {
+                unit: conflict.unit,
+                value: conflict.value,
+            })
+    }
+
+    fn contradiction(&self) -> Option<RudokuError> {
+        (0..self.cells.len())
+            .find(|index| self.candidates[*index] == 0)
+            .map(|index| {
+                let (row, col) = self.cell(index);
+                RudokuError::Contradiction { row, col }
+            })
+    }
+
+    /// Depth-first search over the remaining candidates, branching on the
+    /// empty cell with the fewest possible values. Returns `true` with the
+    /// board filled in if a solution was found, otherwise restores the empty
+    /// cells and returns `false`.
+    fn search(&mut self) -> bool {
+        match self.next_cell() {
+            None => self.board_valid(),
+            Some(((row_index, col_index), mask)) => {
+                for value in mask_values(mask) {
+                    self.fill_cell(row_index, col_index, value);
+                    if self.search() {
+                        return true;
+                    }
+                    self.clear_cell(row_index, col_index);
+                }
+                false
+            }
+        }
+    }
+
+    /// The empty cell with the fewest possible values, along with a mask of
+    /// those values
+    fn next_cell(&self) -> Option<((usize, usize), u32)> {
+        let mut best: Option<((usize, usize), u32)> = None;
+        for index in (0..self.cells.len()).filter(|index| self.cells[*index] == 0) {
+            let cell = self.cell(index);
+            let mask = self.free_mask(cell.0, cell.1);
+            if best.is_none_or(|(_, best_mask)| mask.count_ones() < best_mask.count_ones()) {
+                best = Some((cell, mask));
+                if mask.count_ones() <= 1 {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    /// Count the solutions of the board, stopping once `limit` is reached
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        if limit == 0 || !self.board_valid() {
+            return 0;
+        }
+        let mut board = self.clone();
+        let mut solutions = 0;
+        board.count_search(limit, &mut solutions, &mut |_| {});
+        solutions
+    }
+
+    /// Up to `limit` solutions of the board, in the order the search finds
+    /// them
+    pub(crate) fn solutions(&self, limit: usize) -> Vec<Board> {
+        let mut found = Vec::new();
+        if limit == 0 || !self.board_valid() {
+            return found;
+        }
+        let mut board = self.clone();
+        board.count_search(limit, &mut 0, &mut |solution: &Board| {
+            let mut solution = solution.clone();
+            solution.refresh_candidates();
+            found.push(solution);
+        });
+        found
+    }
+
+    pub fn has_unique_solution(&self) -> bool {
+        self.count_solutions(2) == 1
+    }
+
+    /// Search for solutions, passing each one to `found`
+    fn count_search<F: FnMut(&Board)>(
+        &mut self,
+        limit: usize,
+        solutions: &mut usize,
+        found: &mut F,
+    ) {
+        match self.next_cell() {
+            None => {
+                *solutions += 1;
+                found(self);
+            }
+            Some(((row_index, col_index), mask)) => {
+                for value in mask_values(mask) {
+                    self.fill_cell(row_index, col_index, value);
+                    self.count_search(limit, solutions, found);
+                    self.clear_cell(row_index, col_index);
+                    if *solutions >= limit {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A board string split into cells
+enum Tokens<'a> {
+    Chars(Vec<char>),
+    Words(Vec<&'a str>),
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -82,211 +783,192 @@ pub trait Sudoku {
     fn cols_complete(&self) -> bool;
     fn squares_complete(&self) -> bool;
     fn board_complete(&self) -> bool;
-    fn set_value(&mut self, i: usize, j: usize, value: i32);
+    fn set_value(&mut self, i: usize, j: usize, value: i32) -> Result<(), RudokuError>;
+    fn clear_value(&mut self, i: usize, j: usize) -> Result<(), RudokuError>;
     fn possible_values(&self) -> HashMap<(usize, usize), Vec<i32>>;
     fn solve_tick(&mut self) -> i32;
-    fn solve(&mut self) -> i32;
+    /// Solve the board in place, returning the number of `solve_tick` passes
+    /// taken. A pass that sets nothing is followed by one step of the other
+    /// techniques, and only then by a search, so since those techniques were
+    /// added the count includes a pass for every technique step rather than
+    /// ending at the first search.
+    fn solve(&mut self) -> Result<i32, RudokuError>;
 }
 
 impl Sudoku for Board {
     fn rows(&self) -> Vec<Vec<i32>> {
-        self.board.clone()
+        (0..self.size()).map(|i| self.get_row(i)).collect()
     }
 
     fn cols(&self) -> Vec<Vec<i32>> {
-        (0..WIDTH)
-            .map(|i| {
-                self.board
-                    .clone()
-                    .into_iter()
-                    .map(|row| row[i].clone())
-                    .collect::<Vec<i32>>()
-            })
-            .collect::<Vec<Vec<i32>>>()
+        (0..self.size()).map(|j| self.get_col(j)).collect()
     }
 
     fn squares(&self) -> Vec<Vec<i32>> {
-        // Chunk rows into vertical SQUARE_SIZE
-        let chunked_rows = self
-            .board
-            .chunks(SQUARE_SIZE)
-            .map(|chunk| chunk.to_vec())
-            .collect::<Vec<Vec<Vec<i32>>>>();
-
-        // Chunk the rows into horizontal SQUARE_SIZE
-        let chunked_squares = chunked_rows
-            .iter()
-            .map(|chunk| {
-                chunk
+        (0..self.size())
+            .map(|index| {
+                Unit::Square(index)
+                    .cells(self.shape)
                     .iter()
-                    .map(|row| {
-                        row.chunks(SQUARE_SIZE)
-                            .map(|chunk| chunk.to_vec())
-                            .collect::<Vec<Vec<i32>>>()
-                    })
-                    .collect::<Vec<Vec<Vec<i32>>>>()
+                    .map(|&(i, j)| self.get_value(i, j))
+                    .collect()
             })
-            .collect::<Vec<Vec<Vec<Vec<i32>>>>>();
-
-        // Get vectors of the numbers in each square
-        let squares = chunked_squares
-            .iter()
-            .map(|square_row| {
-                (0..SQUARE_SIZE)
-                    .map(|i| {
-                        square_row
-                            .iter()
-                            .map(|chunked_row| chunked_row.iter().nth(i).unwrap().clone())
-                            .collect::<Vec<Vec<i32>>>()
-                            .concat()
-                    })
-                    .collect::<Vec<Vec<i32>>>()
-            })
-            .collect::<Vec<Vec<Vec<i32>>>>()
-            .concat();
-
-        squares
+            .collect()
     }
 
     fn get_row(&self, row_index: usize) -> Vec<i32> {
-        self.rows()[row_index].clone()
+        self.cells[row_index * self.size()..(row_index + 1) * self.size()]
+            .iter()
+            .map(|value| *value as i32)
+            .collect()
     }
 
     fn get_col(&self, col_index: usize) -> Vec<i32> {
-        self.cols()[col_index].clone()
+        (0..self.size())
+            .map(|i| self.get_value(i, col_index))
+            .collect()
     }
 
     fn get_square(&self, row_index: usize, col_index: usize) -> Vec<i32> {
-        self.squares()
-            .chunks(SQUARE_SIZE)
-            .nth(row_index / SQUARE_SIZE)
-            .unwrap()
+        Unit::square_of(self.shape, row_index, col_index)
+            .cells(self.shape)
             .iter()
-            .nth(col_index / SQUARE_SIZE)
-            .unwrap()
-            .clone()
+            .map(|&(i, j)| self.get_value(i, j))
+            .collect()
     }
 
     fn rows_valid(&self) -> bool {
-        segments_valid(self.rows())
+        self.masks_valid(&self.row_masks, self.filled())
     }
 
     fn cols_valid(&self) -> bool {
-        segments_valid(self.cols())
+        self.masks_valid(&self.col_masks, self.filled())
     }
 
     fn squares_valid(&self) -> bool {
-        segments_valid(self.squares())
+        self.masks_valid(&self.square_masks, self.filled())
     }
 
     fn board_valid(&self) -> bool {
-        self.rows_valid() && self.cols_valid() && self.squares_valid()
+        let filled = self.filled();
+        self.masks_valid(&self.row_masks, filled)
+            && self.masks_valid(&self.col_masks, filled)
+            && self.masks_valid(&self.square_masks, filled)
     }
 
     fn rows_complete(&self) -> bool {
-        segments_complete(self.rows())
+        self.masks_complete(&self.row_masks)
     }
 
     fn cols_complete(&self) -> bool {
-        segments_complete(self.cols())
+        self.masks_complete(&self.col_masks)
     }
 
     fn squares_complete(&self) -> bool {
-        segments_complete(self.squares())
+        self.masks_complete(&self.square_masks)
     }
 
     fn board_complete(&self) -> bool {
         self.rows_complete() && self.cols_complete() && self.squares_complete()
     }
 
-    fn set_value(&mut self, row_index: usize, col_index: usize, value: i32) {
-        self.board[row_index][col_index] = value
+    fn set_value(
+        &mut self,
+        row_index: usize,
+        col_index: usize,
+        value: i32,
+    ) -> Result<(), RudokuError> {
+        if row_index >= self.size() || col_index >= self.size() {
+            return Err(RudokuError::OutOfBounds {
+                row: row_index,
+                col: col_index,
+            });
+        }
+        let max = self.size() as i32;
+        if !(0..=max).contains(&value) {
+            return Err(RudokuError::InvalidValue { value, max });
+        }
+        if self.is_given(row_index, col_index) {
+            return Err(RudokuError::GivenCell {
+                row: row_index,
+                col: col_index,
+            });
+        }
+        if self.get_value(row_index, col_index) != 0 {
+            // The old value's eliminations no longer hold, and with
+            // duplicates on the board its bit may still be owed to a peer
+            let index = self.index(row_index, col_index);
+            self.cells[index] = 0;
+            self.rebuild_masks();
+            self.refresh_candidates();
+        }
+        if value > 0 {
+            self.place(row_index, col_index, value);
+        }
+        Ok(())
+    }
+
+    fn clear_value(&mut self, row_index: usize, col_index: usize) -> Result<(), RudokuError> {
+        self.set_value(row_index, col_index, 0)
     }
 
     fn possible_values(&self) -> HashMap<(usize, usize), Vec<i32>> {
-        let mut possible_values: HashMap<(usize, usize), Vec<i32>> = HashMap::new();
-        (0..HEIGHT).for_each(|row_index| {
-            (0..WIDTH).for_each(|col_index| {
-                let current_value = self
-                    .board
-                    .iter()
-                    .nth(row_index)
-                    .unwrap()
-                    .iter()
-                    .nth(col_index)
-                    .unwrap();
-                let key = (row_index, col_index);
-                let value = match current_value {
-                    x if x > &0 => vec![*x],
-                    _ => vec![1, 2, 3, 4, 5, 6, 7, 8, 9]
-                        .into_iter()
-                        .filter(|number| {
-                            (self.get_row(row_index).iter().all(|i| i != number))
-                                && (self.get_col(col_index).iter().all(|i| i != number))
-                                && (self
-                                    .get_square(row_index, col_index)
-                                    .iter()
-                                    .all(|i| i != number))
-                        })
-                        .collect(),
+        (0..self.cells.len())
+            .map(|index| {
+                let (row_index, col_index) = self.cell(index);
+                let value = match self.cells[index] {
+                    0 => mask_values(self.free_mask(row_index, col_index)),
+                    x => vec![x as i32],
                 };
-                possible_values.insert(key, value);
-            });
-        });
-        possible_values
+                ((row_index, col_index), value)
+            })
+            .collect()
     }
 
     fn solve_tick(&mut self) -> i32 {
         // For values with only one possible value, fill them in
         let mut values_set = 0;
-        let possible_values = self.possible_values();
-        let keys = possible_values.keys();
-        keys.for_each(|key| {
-            let possible = possible_values.get(&key);
-            match possible {
-                Some(possible) => {
-                    if possible.len() == 1 && self.board[key.0][key.1] == 0 {
-                        self.set_value(key.0, key.1, possible[0]);
-                        values_set += 1;
-                        println!("set a value")
-                    }
-                }
-                None => panic!("Tried to solve an invalid cell"),
-            }
+        let singles = (0..self.cells.len())
+            .filter(|index| self.cells[*index] == 0 && self.candidates[*index].count_ones() == 1)
+            .map(|index| (index, self.candidates[index]))
+            .collect::<Vec<(usize, u32)>>();
+        singles.into_iter().for_each(|(index, mask)| {
+            let value = mask.trailing_zeros() as i32 + 1;
+            let (row_index, col_index) = self.cell(index);
+            self.place(row_index, col_index, value);
+            values_set += 1;
         });
         values_set
     }
 
-    fn solve(&mut self) -> i32 {
+    fn solve(&mut self) -> Result<i32, RudokuError> {
+        if let Some(error) = self.duplicate() {
+            return Err(error);
+        }
         let mut steps_taken = 0;
         while !self.board_complete() {
             let values_set = self.solve_tick();
             steps_taken += 1;
+            // Two cells in a unit may have been given the same single value
+            if !self.board_valid() {
+                return Err(RudokuError::Unsolvable);
+            }
             if values_set == 0 {
-                println!("Failed board state");
-                println!("{:?}", self.board);
-                panic!("Could not solve: ran out of definitive possible values")
+                if let Some(error) = self.contradiction() {
+                    return Err(error);
+                }
+                // Naked singles have run out, so try the other techniques
+                if self.solve_step().is_some() {
+                    continue;
+                }
+                // Then fall back to guessing
+                if !self.search() {
+                    return Err(RudokuError::Unsolvable);
+                }
+                self.refresh_candidates();
             }
         }
-        steps_taken
-    }
-}
-
-impl fmt::Display for Board {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            self.board
-                .iter()
-                .map(|row| row
-                    .iter()
-                    .map(|col| col.to_string())
-                    .collect::<Vec<String>>()
-                    .join(" "))
-                .collect::<Vec<String>>()
-                .join("\n")
-        )
+        Ok(steps_taken)
     }
 }
 
@@ -297,22 +979,22 @@ mod tests {
     #[test]
     fn blank_board_from_new() {
         let board = Board::new();
-        assert_eq!(board.board, vec![vec![0; 9]; 9]);
+        assert_eq!(board.rows(), vec![vec![0; 9]; 9]);
     }
 
     #[test]
     fn blank_board_is_valid() {
         let board = Board::new();
-        assert_eq!(board.board_valid(), true);
-        assert_eq!(board.board_complete(), false);
+        assert!(board.board_valid());
+        assert!(!board.board_complete());
     }
 
     #[test]
     fn set_value() {
         let mut board = Board::new();
-        board.set_value(0, 0, 1);
+        board.set_value(0, 0, 1).unwrap();
         assert_eq!(
-            board.board,
+            board.rows(),
             [
                 [1, 0, 0, 0, 0, 0, 0, 0, 0],
                 [0, 0, 0, 0, 0, 0, 0, 0, 0],
@@ -325,9 +1007,9 @@ mod tests {
                 [0, 0, 0, 0, 0, 0, 0, 0, 0],
             ]
         );
-        board.set_value(3, 5, 9);
+        board.set_value(3, 5, 9).unwrap();
         assert_eq!(
-            board.board,
+            board.rows(),
             [
                 [1, 0, 0, 0, 0, 0, 0, 0, 0],
                 [0, 0, 0, 0, 0, 0, 0, 0, 0],
@@ -346,9 +1028,10 @@ mod tests {
     fn correct_board_from_string() {
         let board = Board::from_string(
             "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-        );
+        )
+        .unwrap();
         assert_eq!(
-            board.board,
+            board.rows(),
             [
                 [3, 7, 9, 0, 0, 0, 0, 1, 4],
                 [0, 6, 0, 0, 1, 0, 0, 7, 0],
@@ -367,7 +1050,8 @@ mod tests {
     fn correct_rows() {
         let board = Board::from_string(
             "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-        );
+        )
+        .unwrap();
         assert_eq!(board.rows()[0], [3, 7, 9, 0, 0, 0, 0, 1, 4]);
         assert_eq!(board.rows()[1], [0, 6, 0, 0, 1, 0, 0, 7, 0]);
         assert_eq!(board.rows()[2], [0, 8, 0, 0, 0, 9, 0, 0, 5]);
@@ -383,7 +1067,8 @@ mod tests {
     fn correct_cols() {
         let board = Board::from_string(
             "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-        );
+        )
+        .unwrap();
         assert_eq!(board.cols()[0], [3, 0, 0, 4, 0, 0, 9, 0, 8]);
         assert_eq!(board.cols()[1], [7, 6, 8, 3, 9, 0, 0, 4, 5]);
         assert_eq!(board.cols()[2], [9, 0, 0, 5, 0, 0, 0, 0, 0]);
@@ -399,7 +1084,8 @@ mod tests {
     fn correct_squares() {
         let board = Board::from_string(
             "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-        );
+        )
+        .unwrap();
         assert_eq!(board.squares()[0], [3, 7, 9, 0, 6, 0, 0, 8, 0]);
         assert_eq!(board.squares()[1], [0, 0, 0, 0, 1, 0, 0, 0, 9]);
         assert_eq!(board.squares()[2], [0, 1, 4, 0, 7, 0, 0, 0, 5]);
@@ -415,29 +1101,74 @@ mod tests {
     fn incomplete_valid_board_string_is_valid() {
         let board = Board::from_string(
             "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-        );
-        assert_eq!(board.board_valid(), true);
-        assert_eq!(board.board_complete(), false);
+        )
+        .unwrap();
+        assert!(board.board_valid());
+        assert!(!board.board_complete());
     }
 
     #[test]
-    #[ignore]
     fn complete_valid_board_string_is_valid_and_complete() {
         let board = Board::from_string(
             "845632179732918654196745328683574912457291836219863547361429785574186293928357461",
-        );
-        assert_eq!(board.board_valid(), true);
-        assert_eq!(board.rows_complete(), true);
-        assert_eq!(board.cols_complete(), true);
-        assert_eq!(board.squares_complete(), true);
-        assert_eq!(board.board_complete(), true);
+        )
+        .unwrap();
+        assert!(board.board_valid());
+        assert!(board.rows_complete());
+        assert!(board.cols_complete());
+        assert!(board.squares_complete());
+        assert!(board.board_complete());
+    }
+
+    #[test]
+    fn repeated_digits_are_not_complete() {
+        assert!(!segment_complete(&[5, 5, 5, 5, 5, 5, 5, 5, 5]));
+        assert!(!segment_complete(&[1, 2, 3, 4, 5, 6, 7, 8, 8]));
+        assert!(segment_complete(&[9, 8, 7, 6, 5, 4, 3, 2, 1]));
+
+        let board = Board::from_string(&"555555555".repeat(9)).unwrap();
+        assert!(!board.rows_complete());
+        assert!(!board.cols_complete());
+        assert!(!board.squares_complete());
+        assert!(!board.board_complete());
+        assert!(!board.is_solved());
+    }
+
+    #[test]
+    fn duplicates_are_invalid_until_cleared() {
+        let mut board = Board::from_string(
+            "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
+        )
+        .unwrap();
+        // 7 is already in row 1 and in column 4
+        board.set_value(0, 3, 7).unwrap();
+        assert!(!board.rows_valid());
+        assert!(!board.cols_valid());
+        assert!(board.squares_valid());
+        assert!(!board.board_valid());
+        board.clear_value(0, 3).unwrap();
+        assert!(board.board_valid());
+    }
+
+    #[test]
+    fn is_solved_needs_a_full_valid_grid() {
+        let mut board = Board::from_string(
+            "045632179732918654196745328683574912457291836219863547361429785574186293928357461",
+        )
+        .unwrap();
+        board.set_value(0, 0, 8).unwrap();
+        assert!(board.is_solved());
+        board.clear_value(0, 0).unwrap();
+        assert!(!board.is_solved());
+        assert!(!Board::new().is_solved());
     }
 
     #[test]
     fn test_squares() {
         let board = Board::from_string(
             "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-        );
+        )
+        .unwrap();
         assert_eq!(
             board.squares(),
             vec![
@@ -458,7 +1189,8 @@ mod tests {
     fn test_get_row() {
         let board = Board::from_string(
             "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-        );
+        )
+        .unwrap();
         assert_eq!(board.get_row(0), vec![3, 7, 9, 0, 0, 0, 0, 1, 4]);
         assert_eq!(board.get_row(1), vec![0, 6, 0, 0, 1, 0, 0, 7, 0]);
         assert_eq!(board.get_row(2), vec![0, 8, 0, 0, 0, 9, 0, 0, 5]);
@@ -474,7 +1206,8 @@ mod tests {
     fn test_get_col() {
         let board = Board::from_string(
             "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-        );
+        )
+        .unwrap();
         assert_eq!(board.get_col(0), vec![3, 0, 0, 4, 0, 0, 9, 0, 8]);
         assert_eq!(board.get_col(1), vec![7, 6, 8, 3, 9, 0, 0, 4, 5]);
         assert_eq!(board.get_col(2), vec![9, 0, 0, 5, 0, 0, 0, 0, 0]);
@@ -490,7 +1223,8 @@ mod tests {
     fn test_get_square() {
         let board = Board::from_string(
             "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-        );
+        )
+        .unwrap();
         assert_eq!(board.get_square(0, 0), vec![3, 7, 9, 0, 6, 0, 0, 8, 0]);
         assert_eq!(board.get_square(8, 8), vec![0, 8, 0, 0, 5, 0, 2, 4, 9]);
         assert_eq!(board.get_square(4, 4), vec![0, 0, 7, 0, 4, 0, 8, 0, 0]);
@@ -502,20 +1236,41 @@ mod tests {
     fn test_possible_values() {
         let board = Board::from_string(
             "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-        );
+        )
+        .unwrap();
         assert_eq!(board.possible_values().get(&(0, 0)), Some(&vec![3]));
         assert_eq!(board.possible_values().get(&(1, 0)), Some(&vec![2, 5]));
         assert_eq!(board.possible_values().get(&(0, 3)), Some(&vec![2, 5, 6]));
     }
 
+    #[test]
+    fn possible_values_follow_set_value() {
+        let mut board = Board::new();
+        board.set_value(0, 0, 5).unwrap();
+        assert_eq!(
+            board.possible_values().get(&(0, 8)),
+            Some(&vec![1, 2, 3, 4, 6, 7, 8, 9])
+        );
+        board.set_value(0, 0, 3).unwrap();
+        assert_eq!(
+            board.possible_values().get(&(0, 8)),
+            Some(&vec![1, 2, 4, 5, 6, 7, 8, 9])
+        );
+        assert_eq!(board.cell_candidates(2, 2), vec![1, 2, 4, 5, 6, 7, 8, 9]);
+        board.set_value(0, 0, 0).unwrap();
+        assert_eq!(board.possible_values().get(&(0, 8)).unwrap().len(), 9);
+        assert_eq!(board.cell_candidates(8, 0).len(), 9);
+    }
+
     #[test]
     fn test_solve_tick() {
         let mut board = Board::from_string(
             "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-        );
+        )
+        .unwrap();
         assert_eq!(board.solve_tick(), 2);
         assert_eq!(
-            board.board,
+            board.rows(),
             [
                 [3, 7, 9, 0, 0, 0, 0, 1, 4],
                 [0, 6, 0, 0, 1, 0, 0, 7, 0],
@@ -530,7 +1285,7 @@ mod tests {
         );
         assert_eq!(board.solve_tick(), 2);
         assert_eq!(
-            board.board,
+            board.rows(),
             [
                 [3, 7, 9, 0, 0, 0, 8, 1, 4],
                 [0, 6, 0, 0, 1, 0, 0, 7, 0],
@@ -545,7 +1300,7 @@ mod tests {
         );
         assert_eq!(board.solve_tick(), 3);
         assert_eq!(
-            board.board,
+            board.rows(),
             [
                 [3, 7, 9, 0, 0, 0, 8, 1, 4],
                 [0, 6, 0, 0, 1, 0, 9, 7, 2],
@@ -560,7 +1315,7 @@ mod tests {
         );
         assert_eq!(board.solve_tick(), 4);
         assert_eq!(
-            board.board,
+            board.rows(),
             [
                 [3, 7, 9, 0, 0, 0, 8, 1, 4],
                 [5, 6, 4, 0, 1, 0, 9, 7, 2],
@@ -575,7 +1330,7 @@ mod tests {
         );
         assert_eq!(board.solve_tick(), 3);
         assert_eq!(
-            board.board,
+            board.rows(),
             [
                 [3, 7, 9, 0, 0, 0, 8, 1, 4],
                 [5, 6, 4, 3, 1, 0, 9, 7, 2],
@@ -594,10 +1349,11 @@ mod tests {
     fn test_solve_easy() {
         let mut board = Board::from_string(
             "002000500010705020400090007049000730801030409036000210200080004080902060007000800",
-        );
-        assert_eq!(board.solve(), 12);
+        )
+        .unwrap();
+        assert_eq!(board.solve(), Ok(12));
         assert_eq!(
-            board.board,
+            board.rows(),
             [
                 [9, 7, 2, 8, 6, 3, 5, 4, 1],
                 [6, 1, 8, 7, 4, 5, 9, 2, 3],
@@ -610,29 +1366,304 @@ mod tests {
                 [3, 6, 7, 5, 1, 4, 8, 9, 2]
             ]
         );
-        assert_eq!(board.board_valid(), true);
-        assert_eq!(board.board_complete(), true);
-    }
-
-    // #[test]
-    // fn test_solve() {
-    //     let mut board = Board::from_string(
-    //         "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-    //     );
-    //     assert_eq!(board.solve(), 20);
-    //     assert_eq!(
-    //         board.board,
-    //         [
-    //             [3, 7, 9, 0, 0, 0, 0, 1, 4],
-    //             [0, 6, 0, 0, 1, 0, 0, 7, 0],
-    //             [0, 8, 0, 0, 0, 9, 0, 6, 5],
-    //             [4, 3, 5, 0, 0, 7, 0, 9, 0],
-    //             [0, 9, 0, 0, 4, 0, 0, 2, 0],
-    //             [0, 0, 0, 8, 0, 0, 4, 3, 6],
-    //             [9, 0, 0, 7, 0, 0, 0, 8, 0],
-    //             [0, 4, 0, 0, 8, 0, 0, 5, 0],
-    //             [8, 5, 0, 0, 0, 0, 2, 4, 9],
-    //         ]
-    //     );
-    // }
+        assert!(board.board_valid());
+        assert!(board.board_complete());
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut board = Board::from_string(
+            "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
+        )
+        .unwrap();
+        // Seven passes before the other techniques, when the seventh set
+        // nothing and a search finished the board
+        assert_eq!(board.solve(), Ok(28));
+        assert_eq!(
+            board.rows(),
+            [
+                [3, 7, 9, 5, 2, 6, 8, 1, 4],
+                [5, 6, 4, 3, 1, 8, 9, 7, 2],
+                [2, 8, 1, 4, 7, 9, 3, 6, 5],
+                [4, 3, 5, 2, 6, 7, 1, 9, 8],
+                [6, 9, 8, 1, 4, 3, 5, 2, 7],
+                [7, 1, 2, 8, 9, 5, 4, 3, 6],
+                [9, 2, 3, 7, 5, 4, 6, 8, 1],
+                [1, 4, 6, 9, 8, 2, 7, 5, 3],
+                [8, 5, 7, 6, 3, 1, 2, 4, 9],
+            ]
+        );
+        assert!(board.board_valid());
+        assert!(board.board_complete());
+    }
+
+    #[test]
+    fn from_string_rejects_bad_character() {
+        let result = Board::from_string(
+            "37900001406001007008000900543500700009004002000080043690070008004008005085000024x",
+        );
+        assert_eq!(
+            result.err(),
+            Some(RudokuError::InvalidCharacter {
+                character: 'x',
+                position: 80
+            })
+        );
+    }
+
+    #[test]
+    fn from_string_rejects_wrong_length() {
+        let result = Board::from_string("379000014");
+        assert_eq!(
+            result.err(),
+            Some(RudokuError::InvalidLength {
+                expected: 81,
+                found: 9
+            })
+        );
+    }
+
+    #[test]
+    fn set_value_rejects_bad_input() {
+        let mut board = Board::new();
+        assert_eq!(
+            board.set_value(9, 0, 1),
+            Err(RudokuError::OutOfBounds { row: 9, col: 0 })
+        );
+        assert_eq!(
+            board.set_value(0, 0, 10),
+            Err(RudokuError::InvalidValue { value: 10, max: 9 })
+        );
+    }
+
+    #[test]
+    fn givens_are_locked() {
+        let mut board = Board::from_string(
+            "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
+        )
+        .unwrap();
+        assert!(board.is_given(0, 0));
+        assert!(!board.is_given(0, 3));
+        assert_eq!(
+            board.set_value(0, 0, 1),
+            Err(RudokuError::GivenCell { row: 0, col: 0 })
+        );
+        assert_eq!(
+            board.clear_value(0, 1),
+            Err(RudokuError::GivenCell { row: 0, col: 1 })
+        );
+        assert_eq!(board.get_value(0, 0), 3);
+
+        board.set_value(0, 3, 6).unwrap();
+        board.set_value(0, 3, 5).unwrap();
+        assert!(!board.is_given(0, 3));
+        let givens = board.givens();
+        assert_eq!(givens[0], [3, 7, 9, 0, 0, 0, 0, 1, 4]);
+        board.solve().unwrap();
+        assert_eq!(board.givens(), givens);
+
+        board.reset_to_givens();
+        assert_eq!(board.rows(), givens);
+        assert_eq!(board.cell_candidates(0, 3), vec![2, 5, 6]);
+    }
+
+    #[test]
+    fn from_string_takes_shape_from_length() {
+        let mut board = Board::from_string("1200340000000000").unwrap();
+        assert_eq!(board.shape(), Shape::new(2, 2).unwrap());
+        assert!(board.solve().is_ok());
+        assert!(board.board_valid());
+        assert!(board.board_complete());
+
+        let mut board = Board::from_string("100000020000003000000400000050000006").unwrap();
+        assert_eq!(board.shape(), Shape::new(2, 3).unwrap());
+        assert_eq!(board.get_square(2, 5), vec![0, 0, 0, 4, 0, 0]);
+        assert!(board.solve().is_ok());
+        assert!(board.board_valid());
+        assert!(board.board_complete());
+
+        assert_eq!(
+            Board::from_string("12003400000000000").err(),
+            Some(RudokuError::InvalidLength {
+                expected: 81,
+                found: 17
+            })
+        );
+    }
+
+    #[test]
+    fn from_string_reads_letters_and_tokens() {
+        let board = generate::complete_grid(Shape::new(4, 4).unwrap(), 1);
+        let letters = board.to_string().replace([' ', '\n'], "");
+        assert_eq!(letters.len(), 256);
+        assert!(letters.contains('G'));
+        assert_eq!(Board::from_string(&letters).unwrap().rows(), board.rows());
+        assert_eq!(
+            Board::from_string(&board.to_string()).unwrap().rows(),
+            board.rows()
+        );
+        let numbers = board
+            .rows()
+            .iter()
+            .flatten()
+            .map(|value| value.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        assert_eq!(Board::from_string(&numbers).unwrap().rows(), board.rows());
+
+        assert_eq!(
+            Board::from_string("1 2 0 0 3 4 0 0 0 0 0 0 0 0 0 5").err(),
+            Some(RudokuError::InvalidToken {
+                token: "5".to_string(),
+                position: 15
+            })
+        );
+    }
+
+    #[test]
+    fn from_string_with_shape_allows_tall_boxes() {
+        let shape = Shape::new(3, 2).unwrap();
+        let board =
+            Board::from_string_with_shape("120000000000000000000000000000000000", shape).unwrap();
+        assert_eq!(board.get_square(0, 0), vec![1, 2, 0, 0, 0, 0]);
+        assert_eq!(board.cell_candidates(2, 1), vec![3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn conflicts_list_every_clashing_cell() {
+        assert!(Board::from_string(
+            "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
+        )
+        .unwrap()
+        .conflicts()
+        .is_empty());
+
+        let mut board = Board::new();
+        board.set_value(0, 0, 5).unwrap();
+        board.set_value(0, 7, 5).unwrap();
+        board.set_value(1, 1, 5).unwrap();
+        board.set_value(4, 0, 5).unwrap();
+        assert_eq!(
+            board.conflicts(),
+            vec![
+                Conflict {
+                    unit: Unit::Row(0),
+                    value: 5,
+                    cells: vec![(0, 0), (0, 7)]
+                },
+                Conflict {
+                    unit: Unit::Col(0),
+                    value: 5,
+                    cells: vec![(0, 0), (4, 0)]
+                },
+                Conflict {
+                    unit: Unit::Square(0),
+                    value: 5,
+                    cells: vec![(0, 0), (1, 1)]
+                },
+            ]
+        );
+        assert_eq!(
+            board.conflicts()[0].to_string(),
+            "row 1 has 5 in r1c1, r1c8"
+        );
+    }
+
+    #[test]
+    fn solve_rejects_duplicate_givens() {
+        let mut board = Board::from_string(
+            "379000014060010070080009005435007000090040020000800436900700080040080050850000244",
+        )
+        .unwrap();
+        assert_eq!(
+            board.solve(),
+            Err(RudokuError::DuplicateValue {
+                unit: Unit::Row(8),
+                value: 4
+            })
+        );
+    }
+
+    #[test]
+    fn solve_reports_contradiction() {
+        // Cell (0, 8) sees 1-8 in its row and 9 in its column
+        let mut board = Board::from_string(
+            "123456780000000009000000000000000000000000000000000000000000000000000000000000000",
+        )
+        .unwrap();
+        assert_eq!(
+            board.solve(),
+            Err(RudokuError::Contradiction { row: 0, col: 8 })
+        );
+    }
+
+    #[test]
+    fn count_solutions_of_unique_board() {
+        let board = Board::from_string(
+            "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
+        )
+        .unwrap();
+        assert_eq!(board.count_solutions(10), 1);
+        assert!(board.has_unique_solution());
+        assert_eq!(board.get_row(0), [3, 7, 9, 0, 0, 0, 0, 1, 4]);
+    }
+
+    #[test]
+    fn count_solutions_stops_at_limit() {
+        let board = Board::new();
+        assert_eq!(board.count_solutions(3), 3);
+        assert_eq!(board.count_solutions(0), 0);
+        assert!(!board.has_unique_solution());
+    }
+
+    #[test]
+    fn count_solutions_of_broken_board() {
+        let board = Board::from_string(
+            "379000014060010070080009005435007000090040020000800436900700080040080050850000244",
+        )
+        .unwrap();
+        assert_eq!(board.count_solutions(2), 0);
+        let board = Board::from_string(
+            "123456780000000009000000000000000000000000000000000000000000000000000000000000000",
+        )
+        .unwrap();
+        assert_eq!(board.count_solutions(2), 0);
+        assert!(!board.has_unique_solution());
+    }
+
+    #[test]
+    fn solve_trace_explains_steps() {
+        let mut board = Board::from_string(
+            "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
+        )
+        .unwrap();
+        let steps = board.solve_trace().unwrap();
+        assert!(board.board_complete());
+        assert_eq!(
+            steps
+                .iter()
+                .map(|step| step.placements.len())
+                .sum::<usize>(),
+            48
+        );
+        assert!(steps
+            .iter()
+            .all(|step| step.technique != TechniqueKind::Guess));
+        assert_eq!(steps[0].to_string(), "Naked single: r3c8 can only be 6");
+    }
+
+    #[test]
+    fn solve_trace_guesses_when_stuck() {
+        // Two solutions, with 3 and 5 swapping places in r1c1, r1c4, r2c1, r2c4
+        let mut board = Board::from_string(
+            "079026814064018972281479365435267198698143527712895436923754681146982753857631249",
+        )
+        .unwrap();
+        let steps = board.solve_trace().unwrap();
+        assert!(board.board_valid());
+        assert!(board.board_complete());
+        assert_eq!(steps[0].technique, TechniqueKind::Guess);
+        assert_eq!(steps[0].to_string(), "Guess: try 3 in r1c1");
+        assert_eq!(board.get_row(0), [3, 7, 9, 5, 2, 6, 8, 1, 4]);
+    }
 }
@@ -1,14 +1,807 @@
-use rudoku::{Board, Sudoku};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process;
+
+use rudoku::batch::{self, BatchResult, BatchSummary, ParallelOptions};
+use rudoku::cnf::{Cnf, Variant};
+use rudoku::formats::{self, ExplainerRating, Puzzle};
+use rudoku::generate::{self, GeneratorOptions, Symmetry};
+use rudoku::solver::{Backend, Solver};
+use rudoku::{looks_like_puzzle, Board, Difficulty, DisplayStyle, HintLevel, RudokuError, Shape};
+
+const USAGE: &str = "\
+Usage: rudoku <command> [options] [puzzle...]
+
+Commands:
+    solve       Solve each puzzle
+    validate    Report conflicts, and whether each puzzle has one solution
+    generate    Generate new puzzles
+    rate        Rate how hard each puzzle is to solve by logic
+    hint        Explain the simplest next step for each puzzle
+    convert     Write each puzzle in another format
+    batch       Solve files of one puzzle per line as they are read, writing
+                CSV, or JSON with --format json, and a summary to stderr
+    help        Print this message
+
+Puzzles are read from the arguments, which may be puzzles or files (.sdk,
+.sdm, .ss, or one puzzle per line), or from standard input when there are
+no arguments or one of them is `-`.
+
+Options:
+    -f, --format <plain|pretty|json>    How to print results [default: plain]
+    --to <sdk|sdm|ss|explainer|cnf>     Format for `convert` [default: sdm]
+    --variant <diagonals|anti-knight|anti-king>
+                                        Extra rules for `--to cnf`, separated
+                                        by commas or given more than once
+    --level <region|technique|step>     How much a `hint` gives away
+                                        [default: step]
+    --solver <propagation|dlx>          Engine for finding solutions
+                                        [default: propagation]
+    --clues <n>                         Givens to aim for [default: 30]
+    --seed <n>                          Seed for the first puzzle [default: 0]
+    --symmetry <none|rotational|mirror|diagonal>
+                                        Pattern of the givens [default: none]
+    --shape <rows>x<cols>               Box shape [default: 3x3]
+    --difficulty <easy|medium|hard|expert>
+                                        Rating for `generate` to aim for
+    --count <n>                         Puzzles to generate [default: 1]
+    --threads <n>                       Threads for `batch`, 0 for one per
+                                        core [default: 0]
+    --unordered                         Let `batch` write results as they
+                                        finish rather than in input order
+
+Exit status, for the worst puzzle:
+    0   solved, or valid with a single solution
+    1   could not be read, or has conflicting values
+    2   the command line was not understood
+    3   has more than one solution
+    4   has no solution
+    5   could not be solved, rated, converted or generated
+";
+
+/// Exit code for a command line that was not understood
+const USAGE_ERROR: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Solve,
+    Validate,
+    Generate,
+    Rate,
+    Hint,
+    Convert,
+    Batch,
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// One line per puzzle
+    Plain,
+    /// Boxed grids and explanations
+    Pretty,
+    /// One JSON object per puzzle, in the schema of the `serde` feature
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+/// The file formats `convert` can write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Sdk,
+    Sdm,
+    Ss,
+    Explainer,
+    /// DIMACS CNF, for SAT solvers
+    Cnf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Options {
+    command: Command,
+    format: Format,
+    to: Target,
+    variants: Vec<Variant>,
+    backend: Backend,
+    level: HintLevel,
+    generator: GeneratorOptions,
+    count: usize,
+    parallel: ParallelOptions,
+    inputs: Vec<String>,
+}
+
+/// What was found for a puzzle, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Status {
+    Solved,
+    Invalid,
+    Multiple,
+    Unsolvable,
+    Failed,
+}
+
+impl Status {
+    fn exit_code(self) -> i32 {
+        match self {
+            Status::Solved => 0,
+            Status::Invalid => 1,
+            Status::Multiple => 3,
+            Status::Unsolvable => 4,
+            Status::Failed => 5,
+        }
+    }
+
+    /// The name used in JSON output
+    #[cfg(feature = "serde")]
+    fn name(self) -> &'static str {
+        match self {
+            Status::Solved => "solved",
+            Status::Invalid => "invalid",
+            Status::Multiple => "multiple_solutions",
+            Status::Unsolvable => "unsolvable",
+            Status::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Status::Solved => "valid with a single solution",
+            Status::Invalid => "conflicting values",
+            Status::Multiple => "more than one solution",
+            Status::Unsolvable => "no solution",
+            Status::Failed => "failed",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<u64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value {:?} for {}", value, flag))
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options {
+        command: Command::Help,
+        format: Format::Plain,
+        to: Target::Sdm,
+        variants: Vec::new(),
+        backend: Backend::default(),
+        level: HintLevel::Step,
+        generator: GeneratorOptions::default(),
+        count: 1,
+        parallel: ParallelOptions::default(),
+        inputs: Vec::new(),
+    };
+    let mut command = None;
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') || arg == "-" {
+            match command {
+                None => command = Some(arg),
+                Some(_) => options.inputs.push(arg),
+            }
+            continue;
+        }
+        if arg == "-h" || arg == "--help" {
+            command = Some("help".to_string());
+            continue;
+        }
+        if arg == "--unordered" {
+            options.parallel.ordered = false;
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        let invalid = || format!("invalid value {:?} for {}", value, arg);
+        match arg.as_str() {
+            "-f" | "--format" => {
+                options.format = match value.as_str() {
+                    "plain" => Format::Plain,
+                    "pretty" => Format::Pretty,
+                    #[cfg(feature = "serde")]
+                    "json" => Format::Json,
+                    #[cfg(not(feature = "serde"))]
+                    "json" => return Err("json output needs the serde feature".to_string()),
+                    _ => return Err(invalid()),
+                }
+            }
+            "--to" => {
+                options.to = match value.as_str() {
+                    "sdk" => Target::Sdk,
+                    "sdm" => Target::Sdm,
+                    "ss" => Target::Ss,
+                    "explainer" => Target::Explainer,
+                    "cnf" => Target::Cnf,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--level" => {
+                options.level = match value.as_str() {
+                    "region" => HintLevel::Region,
+                    "technique" => HintLevel::Technique,
+                    "step" => HintLevel::Step,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--variant" => {
+                for name in value.split(',') {
+                    options.variants.push(match name {
+                        "diagonals" => Variant::Diagonals,
+                        "anti-knight" => Variant::AntiKnight,
+                        "anti-king" => Variant::AntiKing,
+                        _ => return Err(invalid()),
+                    });
+                }
+            }
+            "--solver" => {
+                options.backend = match value.as_str() {
+                    "propagation" => Backend::Propagation,
+                    "dlx" => Backend::DancingLinks,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--clues" => options.generator.clues = parse_number(&arg, &value)? as usize,
+            "--seed" => options.generator.seed = parse_number(&arg, &value)?,
+            "--count" => options.count = parse_number(&arg, &value)? as usize,
+            "--threads" => options.parallel.threads = parse_number(&arg, &value)? as usize,
+            "--symmetry" => {
+                options.generator.symmetry = match value.as_str() {
+                    "none" => Symmetry::None,
+                    "rotational" => Symmetry::Rotational,
+                    "mirror" => Symmetry::Mirror,
+                    "diagonal" => Symmetry::Diagonal,
+                    _ => return Err(invalid()),
+                }
+            }
+            "--difficulty" => {
+                options.generator.difficulty = Some(match value.as_str() {
+                    "easy" => Difficulty::Easy,
+                    "medium" => Difficulty::Medium,
+                    "hard" => Difficulty::Hard,
+                    "expert" => Difficulty::Expert,
+                    _ => return Err(invalid()),
+                })
+            }
+            "--shape" => {
+                let (rows, cols) = value.split_once('x').ok_or_else(invalid)?;
+                let rows = parse_number(&arg, rows)? as usize;
+                let cols = parse_number(&arg, cols)? as usize;
+                options.generator.shape = Shape::new(rows, cols).map_err(|e| e.to_string())?;
+            }
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+    options.command = match command.as_deref() {
+        Some("solve") => Command::Solve,
+        Some("validate") => Command::Validate,
+        Some("generate") => Command::Generate,
+        Some("rate") => Command::Rate,
+        Some("hint") => Command::Hint,
+        Some("convert") => Command::Convert,
+        Some("batch") => Command::Batch,
+        Some("help") => Command::Help,
+        Some(other) => return Err(format!("unknown command {:?}", other)),
+        None => return Err("no command given".to_string()),
+    };
+    if options.inputs.is_empty() {
+        options.inputs.push("-".to_string());
+    }
+    Ok(options)
+}
+
+/// Read the puzzles from a command line argument: `-` for standard input,
+/// a file, or a puzzle itself if it looks like one
+fn read_input(input: &str) -> Result<Vec<Puzzle>, String> {
+    if input == "-" {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| e.to_string())?;
+        return formats::read_puzzles(&text).map_err(|e| e.to_string());
+    }
+    let path = Path::new(input);
+    if !path.is_file() {
+        return match looks_like_puzzle(input) {
+            true => formats::read_puzzles(input).map_err(|e| e.to_string()),
+            false => Err("no such file".to_string()),
+        };
+    }
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let puzzles = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("sdk") => formats::read_sdk(&text).map(|puzzle| vec![puzzle]),
+        Some("sdm") => {
+            formats::read_sdm(&text).map(|boards| boards.into_iter().map(Puzzle::from).collect())
+        }
+        Some("ss") => formats::read_ss(&text).map(|board| vec![Puzzle::from(board)]),
+        _ => formats::read_puzzles(&text),
+    };
+    puzzles.map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "serde")]
+fn json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("results serialize to JSON")
+}
+
+/// Whether a board can be solved, and only one way
+fn check(board: &Board, solver: &dyn Solver) -> Status {
+    if !board.conflicts().is_empty() {
+        return Status::Invalid;
+    }
+    match solver.count_solutions(board, 2) {
+        0 => Status::Unsolvable,
+        1 => Status::Solved,
+        _ => Status::Multiple,
+    }
+}
+
+fn solve(board: &Board, solver: &dyn Solver, format: Format) -> (Status, String) {
+    let (status, solution) = match check(board, solver) {
+        Status::Solved => match solver.solve(board) {
+            Ok(solution) => (Status::Solved, Ok(Some(solution))),
+            Err(error) => (Status::Failed, Err(error)),
+        },
+        status => (status, Ok(None)),
+    };
+    let text = match (format, &solution) {
+        #[cfg(feature = "serde")]
+        (Format::Json, _) => json(&serde_json::json!({
+            "status": status.name(),
+            "puzzle": board,
+            "solution": solution.as_ref().ok(),
+            "error": solution.as_ref().err().map(ToString::to_string),
+        })),
+        (_, Err(error)) => error.to_string(),
+        (Format::Plain, Ok(Some(solution))) => solution.display(DisplayStyle::Line).to_string(),
+        (Format::Pretty, Ok(Some(solution))) => format!("{:#}", solution),
+        _ => status.to_string(),
+    };
+    (status, text)
+}
+
+fn validate(board: &Board, solver: &dyn Solver, format: Format) -> (Status, String) {
+    let status = check(board, solver);
+    let conflicts = board.conflicts();
+    let report = conflicts
+        .iter()
+        .map(|conflict| conflict.to_string())
+        .chain(Some(status.to_string()).filter(|_| conflicts.is_empty()))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let text = match format {
+        Format::Plain => report,
+        Format::Pretty => format!("{:#}\n{}", board, report),
+        #[cfg(feature = "serde")]
+        Format::Json => json(&serde_json::json!({
+            "status": status.name(),
+            "conflicts": conflicts,
+        })),
+    };
+    (status, text)
+}
+
+fn rate(board: &Board, solver: &dyn Solver, format: Format) -> (Status, String) {
+    let (status, report) = match check(board, solver) {
+        Status::Solved => match board.report() {
+            Ok(report) => (Status::Solved, Ok(Some(report))),
+            Err(error) => (Status::Failed, Err(error)),
+        },
+        status => (status, Ok(None)),
+    };
+    let text = match (format, &report) {
+        #[cfg(feature = "serde")]
+        (Format::Json, _) => json(&serde_json::json!({
+            "status": status.name(),
+            "rating": match &report {
+                Ok(Some(report)) => Some(&report.rating),
+                _ => None,
+            },
+            "error": report.as_ref().err().map(ToString::to_string),
+        })),
+        (_, Err(error)) => error.to_string(),
+        (Format::Plain, Ok(Some(report))) => report.rating.to_string(),
+        (Format::Pretty, Ok(Some(report))) => report
+            .steps
+            .iter()
+            .map(|step| step.to_string())
+            .chain(Some(report.rating.to_string()))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        _ => status.to_string(),
+    };
+    (status, text)
+}
+
+fn hint(board: &Board, solver: &dyn Solver, options: &Options) -> (Status, String) {
+    let status = check(board, solver);
+    let hint = match status {
+        Status::Invalid => Ok(None),
+        _ => board.hint(options.level),
+    };
+    // A puzzle without a solution already exits non-zero
+    let status = match (status, &hint) {
+        (Status::Solved, Err(_)) => Status::Failed,
+        _ => status,
+    };
+    let explanation = match (&hint, status) {
+        (Ok(Some(hint)), _) => hint.to_string(),
+        (Err(error), _) => error.to_string(),
+        (Ok(None), Status::Invalid) => status.to_string(),
+        (Ok(None), _) => "no step found".to_string(),
+    };
+    let text = match options.format {
+        Format::Plain => explanation,
+        Format::Pretty => format!("{:#}\n{}", board, explanation),
+        #[cfg(feature = "serde")]
+        Format::Json => {
+            let error = hint.as_ref().err().map(ToString::to_string);
+            let hint = hint.ok().flatten();
+            json(&serde_json::json!({
+                "status": status.name(),
+                // The whole step, as before there were hint levels, and so
+                // only at `--level step`
+                "step": hint.as_ref().and_then(|hint| hint.step.as_ref()),
+                "hint": hint,
+                "error": error,
+            }))
+        }
+    };
+    (status, text)
+}
+
+fn convert(puzzle: &Puzzle, options: &Options) -> (Status, String) {
+    #[cfg(feature = "serde")]
+    {
+        if options.format == Format::Json {
+            return (Status::Solved, json(puzzle));
+        }
+    }
+    let board = &puzzle.board;
+    let text = match options.to {
+        Target::Sdk => formats::write_sdk(puzzle),
+        Target::Sdm => formats::write_sdm(std::slice::from_ref(board)),
+        Target::Ss => formats::write_ss(board),
+        Target::Explainer => match ExplainerRating::from_board(board) {
+            Ok(rating) => formats::write_explainer(board, Some(&rating)),
+            Err(error) => return (Status::Failed, error.to_string()),
+        },
+        Target::Cnf => Cnf::encode(board, &options.variants).to_string(),
+    };
+    (Status::Solved, text.trim_end().to_string())
+}
+
+fn generate<W: Write>(options: &Options, out: &mut W) -> io::Result<Status> {
+    for offset in 0..options.count as u64 {
+        let board = match generate::generate(&GeneratorOptions {
+            seed: options.generator.seed.wrapping_add(offset),
+            ..options.generator
+        }) {
+            Ok(board) => board,
+            Err(error) => {
+                eprintln!("rudoku: {}", error);
+                return Ok(Status::Failed);
+            }
+        };
+        match options.format {
+            Format::Plain => writeln!(out, "{}", board.display(DisplayStyle::Line))?,
+            Format::Pretty => writeln!(out, "{:#}\n", board)?,
+            #[cfg(feature = "serde")]
+            Format::Json => writeln!(out, "{}", json(&board))?,
+        }
+    }
+    Ok(Status::Solved)
+}
+
+fn batch_status(result: &BatchResult) -> Status {
+    match (&result.error, result.solutions) {
+        (Some(RudokuError::DuplicateValue { .. }), _) | (Some(RudokuError::AtLine { .. }), _) => {
+            Status::Invalid
+        }
+        (_, 0) => Status::Unsolvable,
+        (_, 1) => Status::Solved,
+        _ => Status::Multiple,
+    }
+}
+
+fn batch<W: Write>(options: &Options, out: &mut W) -> io::Result<Status> {
+    let mut summary = BatchSummary::new();
+    let mut worst = Status::Solved;
+    match options.format {
+        #[cfg(feature = "serde")]
+        Format::Json => {}
+        _ => writeln!(out, "{}", batch::CSV_HEADER)?,
+    }
+    for input in &options.inputs {
+        let reader: Box<dyn BufRead + Send> = match input.as_str() {
+            "-" => Box::new(BufReader::new(io::stdin())),
+            path => match File::open(path) {
+                Ok(file) => Box::new(BufReader::new(file)),
+                Err(error) => {
+                    eprintln!("rudoku: {}: {}", input, error);
+                    worst = worst.max(Status::Invalid);
+                    continue;
+                }
+            },
+        };
+        for result in batch::solve_lines_parallel(reader, options.parallel) {
+            let mut result = match result {
+                Ok(result) => result,
+                Err(error) => {
+                    eprintln!("rudoku: {}: {}", input, error);
+                    worst = worst.max(Status::Invalid);
+                    break;
+                }
+            };
+            // Number the puzzles across all of the inputs
+            result.index = summary.puzzles;
+            match options.format {
+                #[cfg(feature = "serde")]
+                Format::Json => writeln!(out, "{}", result.json_row())?,
+                _ => writeln!(out, "{}", result.csv_row())?,
+            }
+            summary.add(&result);
+            worst = worst.max(batch_status(&result));
+        }
+    }
+    eprintln!("{}", summary);
+    Ok(worst)
+}
+
+/// Run a command, writing results to `out`. Returns the worst status found,
+/// or the error that stopped output, such as a closed pipe.
+fn run<W: Write>(options: &Options, out: &mut W) -> io::Result<Status> {
+    match options.command {
+        Command::Help => return write!(out, "{}", USAGE).map(|_| Status::Solved),
+        Command::Generate => return generate(options, out),
+        Command::Batch => return batch(options, out),
+        _ => {}
+    }
+    let mut worst = Status::Solved;
+    for input in &options.inputs {
+        let puzzles = match read_input(input) {
+            Ok(puzzles) => puzzles,
+            Err(message) => {
+                eprintln!("rudoku: {}: {}", input, message);
+                worst = worst.max(Status::Invalid);
+                continue;
+            }
+        };
+        for puzzle in &puzzles {
+            let solver = options.backend.solver();
+            let (status, text) = match options.command {
+                Command::Solve => solve(&puzzle.board, solver, options.format),
+                Command::Validate => validate(&puzzle.board, solver, options.format),
+                Command::Rate => rate(&puzzle.board, solver, options.format),
+                Command::Hint => hint(&puzzle.board, solver, options),
+                _ => convert(puzzle, options),
+            };
+            writeln!(out, "{}", text)?;
+            worst = worst.max(status);
+        }
+    }
+    Ok(worst)
+}
 
 fn main() {
-    let board: Board = Board::from_string(
-        "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
-    );
-    println!("{}", board);
-    println!("Rows valid: {}", board.rows_valid());
-    println!("Cols valid: {}", board.cols_valid());
-    println!("Valid: {}", board.board_valid());
-    println!("Rows complete: {}", board.rows_complete());
-    println!("Cols complete: {}", board.cols_complete());
-    println!("Complete: {}", board.board_complete());
+    let options = parse_args(std::env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("rudoku: {}\n\n{}", message, USAGE);
+        process::exit(USAGE_ERROR);
+    });
+    let stdout = io::stdout();
+    match run(&options, &mut stdout.lock()) {
+        Ok(status) => process::exit(status.exit_code()),
+        // Whoever was reading has stopped, as when piped into `head`
+        Err(error) if error.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(error) => {
+            eprintln!("rudoku: {}", error);
+            process::exit(Status::Invalid.exit_code());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rudoku::solver::DlxSolver;
+
+    const PUZZLE: &str =
+        "379000014060010070080009005435007000090040020000800436900700080040080050850000249";
+
+    fn args(line: &str) -> Result<Options, String> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn parses_commands_and_options() {
+        let options = args("generate --clues 40 --seed 7 --symmetry mirror --shape 2x3").unwrap();
+        assert_eq!(options.command, Command::Generate);
+        assert_eq!(options.generator.clues, 40);
+        assert_eq!(options.generator.seed, 7);
+        assert_eq!(options.generator.symmetry, Symmetry::Mirror);
+        assert_eq!(options.generator.shape, Shape::new(2, 3).unwrap());
+        assert_eq!(options.generator.difficulty, None);
+        let options = args("generate --difficulty hard").unwrap();
+        assert_eq!(options.generator.difficulty, Some(Difficulty::Hard));
+        assert!(args("generate --difficulty tricky").is_err());
+
+        let options =
+            args("convert --to cnf --variant diagonals,anti-knight --variant anti-king").unwrap();
+        assert_eq!(
+            options.variants,
+            [Variant::Diagonals, Variant::AntiKnight, Variant::AntiKing]
+        );
+        assert!(args("convert --variant anti-queen").is_err());
+
+        let options = args("batch --threads 3 --unordered").unwrap();
+        assert_eq!(options.parallel.threads, 3);
+        assert!(!options.parallel.ordered);
+
+        let options = args("solve -f pretty a.sdm - b").unwrap();
+        assert_eq!(options.format, Format::Pretty);
+        assert_eq!(options.inputs, vec!["a.sdm", "-", "b"]);
+        assert_eq!(args("rate").unwrap().inputs, vec!["-"]);
+        assert_eq!(args("--help").unwrap().command, Command::Help);
+
+        assert!(args("").is_err());
+        assert!(args("unscramble").is_err());
+        assert!(args("solve --format fancy").is_err());
+        assert!(args("generate --clues").is_err());
+        assert!(args("generate --shape 9x9").is_err());
+        assert_eq!(
+            args("solve --solver dlx").unwrap().backend,
+            Backend::DancingLinks
+        );
+        assert_eq!(
+            args("hint --level region").unwrap().level,
+            HintLevel::Region
+        );
+    }
+
+    #[test]
+    fn status_reflects_the_solutions() {
+        let board = Board::from_string(PUZZLE).unwrap();
+        Backend::ALL.iter().for_each(|backend| {
+            let (status, text) = solve(&board, backend.solver(), Format::Plain);
+            assert_eq!(status, Status::Solved);
+            assert!(text.starts_with("379526814"));
+
+            let (status, text) = solve(&Board::new(), backend.solver(), Format::Plain);
+            assert_eq!(status, Status::Multiple);
+            assert_eq!(text, "more than one solution");
+        });
+
+        let clash = Board::from_string(&PUZZLE.replacen('0', "3", 1)).unwrap();
+        let (status, text) = validate(&clash, &DlxSolver, Format::Plain);
+        assert_eq!(status, Status::Invalid);
+        assert_eq!(text.lines().next(), Some("row 1 has 3 in r1c1, r1c4"));
+        assert_eq!(status.max(Status::Multiple).exit_code(), 3);
+
+        let unsolvable = Board::from_string(&PUZZLE.replacen('0', "2", 1)).unwrap();
+        assert_eq!(check(&unsolvable, &DlxSolver), Status::Unsolvable);
+    }
+
+    #[test]
+    fn runs_each_puzzle_given() {
+        let options = args(&format!("solve {} {}", PUZZLE, &PUZZLE[1..])).unwrap();
+        let mut out = Vec::new();
+        assert_eq!(run(&options, &mut out).unwrap(), Status::Invalid);
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 1);
+
+        let options = args("generate --count 2 --clues 40 --seed 3").unwrap();
+        let mut out = Vec::new();
+        assert_eq!(run(&options, &mut out).unwrap(), Status::Solved);
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().count(), 2);
+        assert_ne!(text.lines().next(), text.lines().nth(1));
+
+        let path = std::env::temp_dir().join(format!("rudoku-batch-{}.sdm", process::id()));
+        fs::write(&path, format!("{}\n{}x\n", PUZZLE, &PUZZLE[1..])).unwrap();
+        let options = args(&format!("batch {}", path.display())).unwrap();
+        let mut out = Vec::new();
+        assert_eq!(run(&options, &mut out).unwrap(), Status::Invalid);
+        fs::remove_file(&path).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().next(), Some(batch::CSV_HEADER));
+        assert!(text.lines().nth(1).unwrap().starts_with("0,379"));
+        assert!(text.lines().nth(2).unwrap().starts_with("1,,0,,"));
+
+        let options = args(&format!("convert --to ss {}", PUZZLE)).unwrap();
+        let mut out = Vec::new();
+        run(&options, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().next(), Some("379|...|.14"));
+
+        let options = args(&format!("convert --to cnf --variant diagonals {}", PUZZLE)).unwrap();
+        let mut out = Vec::new();
+        run(&options, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let plain = Cnf::encode(&Board::from_string(PUZZLE).unwrap(), &[]);
+        assert!(text.lines().count() > plain.clauses.len() + 2);
+    }
+
+    #[test]
+    fn errors_are_printed_and_fail() {
+        let unsolvable = PUZZLE.replacen('0', "2", 1);
+        let options = args(&format!("convert --to explainer {}", unsolvable)).unwrap();
+        let mut out = Vec::new();
+        assert_eq!(run(&options, &mut out).unwrap(), Status::Failed);
+        let board = Board::from_string(&unsolvable).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap().trim_end(),
+            ExplainerRating::from_board(&board).unwrap_err().to_string()
+        );
+        assert_eq!(Status::Failed.exit_code(), 5);
+    }
+
+    #[test]
+    fn hints_report_why_there_is_none() {
+        let solver = Backend::default().solver();
+        let options = args("hint").unwrap();
+        let (_, text) = hint(&Board::from_string(PUZZLE).unwrap(), solver, &options);
+        assert_eq!(text, "Naked single: r3c8 can only be 6");
+
+        let unsolvable = Board::from_string(&PUZZLE.replacen('0', "2", 1)).unwrap();
+        let (status, text) = hint(&unsolvable, solver, &options);
+        assert_eq!(status, Status::Unsolvable);
+        assert_eq!(
+            text,
+            unsolvable.hint(HintLevel::Step).unwrap_err().to_string()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_hints_keep_the_step() {
+        let solver = Backend::default().solver();
+        let board = Board::from_string(PUZZLE).unwrap();
+        let (_, text) = hint(&board, solver, &args("hint -f json").unwrap());
+        let value = serde_json::from_str::<serde_json::Value>(&text).unwrap();
+        assert_eq!(
+            value["step"],
+            serde_json::to_value(board.find_step()).unwrap()
+        );
+        assert_eq!(value["hint"]["level"], "step");
+        assert!(value["error"].is_null());
+
+        let (_, text) = hint(
+            &board,
+            solver,
+            &args("hint -f json --level region").unwrap(),
+        );
+        let value = serde_json::from_str::<serde_json::Value>(&text).unwrap();
+        assert!(value["step"].is_null());
+
+        let unsolvable = Board::from_string(&PUZZLE.replacen('0', "2", 1)).unwrap();
+        let (_, text) = hint(&unsolvable, solver, &args("hint -f json").unwrap());
+        let value = serde_json::from_str::<serde_json::Value>(&text).unwrap();
+        assert!(value["hint"].is_null());
+        assert!(value["error"].is_string());
+    }
+
+    #[test]
+    fn mistyped_paths_are_not_read_as_puzzles() {
+        assert_eq!(
+            read_input("puzles.sdm").err().as_deref(),
+            Some("no such file")
+        );
+        assert_eq!(read_input("2024").err().as_deref(), Some("no such file"));
+        assert_eq!(read_input(PUZZLE).unwrap().len(), 1);
+        assert_eq!(read_input(&PUZZLE.replace('0', "_")).unwrap().len(), 1);
+        assert_eq!(
+            read_input(&PUZZLE[1..]).err(),
+            Some(
+                RudokuError::InvalidLength {
+                    expected: 81,
+                    found: 80
+                }
+                .to_string()
+            )
+        );
+    }
 }
@@ -0,0 +1,178 @@
+use std::str::FromStr;
+
+use crate::shape::MAX_SIZE;
+use crate::{char_value, Board, RudokuError, Shape};
+
+/// Characters that stand for an empty cell
+const BLANKS: [char; 5] = ['.', '0', '_', '*', '-'];
+/// Characters that only draw the grid
+pub(crate) const BORDERS: [char; 2] = ['|', '+'];
+
+/// A cell read from a puzzle, with its 1-based line and column
+type Cell = (char, usize, usize);
+
+/// Whether a line made up of dashes is a row of blank cells rather than a
+/// rule between bands of boxes. Rules are drawn across the box borders too,
+/// so they are longer than the rows of cells around them.
+fn is_blank_row(line: &str, row_width: Option<usize>) -> bool {
+    let dashes = line.chars().filter(|c| *c == '-').count();
+    row_width.is_none_or(|width| dashes == width)
+}
+
+fn is_rule(line: &str) -> bool {
+    line.contains('-')
+        && line
+            .chars()
+            .all(|c| c == '-' || c == '|' || c.is_whitespace())
+}
+
+fn line_cells(line_index: usize, line: &str) -> Vec<Cell> {
+    line.chars()
+        .enumerate()
+        .filter(|(_, c)| !c.is_whitespace() && !BORDERS.contains(c))
+        .map(|(column, c)| (c, line_index + 1, column + 1))
+        .collect()
+}
+
+/// Whether text could be a puzzle for `Board::from_str`: only values,
+/// blanks, borders and whitespace, with at least as many cells as the
+/// smallest board. A puzzle a few cells out still counts, so that parsing
+/// it says what is wrong.
+pub fn looks_like_puzzle(text: &str) -> bool {
+    let smallest = Shape::new(2, 2).map_or(16, Shape::cell_count);
+    text.chars().all(|c| {
+        c.is_whitespace()
+            || BORDERS.contains(&c)
+            || BLANKS.contains(&c)
+            || char_value(c, MAX_SIZE).is_some()
+    }) && line_cells(0, text).len() >= smallest
+}
+
+/// Parse a puzzle in any of the common notations: blanks as `.`, `0`, `_`,
+/// `*` or `-`, with whitespace and box borders drawn in `|`, `+` and `-`
+/// skipped, so boxed grids printed by other tools read back in. The shape is
+/// taken from the number of cells, as in `Board::from_string`, so values
+/// beyond 9 must be letters.
+impl FromStr for Board {
+    type Err = RudokuError;
+
+    fn from_str(puzzle: &str) -> Result<Self, Self::Err> {
+        let lines = puzzle
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.contains('+'))
+            .collect::<Vec<(usize, &str)>>();
+        let row_width = lines
+            .iter()
+            .filter(|(_, line)| !is_rule(line))
+            .map(|(line_index, line)| line_cells(*line_index, line).len())
+            .find(|width| *width > 0);
+        let cells = lines
+            .into_iter()
+            .filter(|(_, line)| !is_rule(line) || is_blank_row(line, row_width))
+            .flat_map(|(line_index, line)| line_cells(line_index, line))
+            .collect::<Vec<Cell>>();
+
+        let shape = Board::shape_for_cells(cells.len())?;
+        let values = cells
+            .into_iter()
+            .map(|(character, line, column)| {
+                if BLANKS.contains(&character) {
+                    return Ok(0);
+                }
+                char_value(character, shape.size()).ok_or(RudokuError::UnexpectedCharacter {
+                    character,
+                    line,
+                    column,
+                })
+            })
+            .collect::<Result<Vec<u8>, RudokuError>>()?;
+        Board::from_values(shape, values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{DisplayStyle, Sudoku};
+
+    const PUZZLE: &str =
+        "379000014060010070080009005435007000090040020000800436900700080040080050850000249";
+
+    fn puzzle() -> Board {
+        Board::from_string(PUZZLE).unwrap()
+    }
+
+    #[test]
+    fn reads_every_blank_notation() {
+        ['.', '0', '_', '*', '-'].iter().for_each(|blank| {
+            let line = PUZZLE.replace('0', &blank.to_string());
+            assert_eq!(line.parse::<Board>().unwrap().rows(), puzzle().rows());
+        });
+    }
+
+    #[test]
+    fn reads_boxed_grids() {
+        let grid = format!("{:#}", puzzle());
+        assert_eq!(grid.parse::<Board>().unwrap().rows(), puzzle().rows());
+        let plain = puzzle().to_string();
+        assert_eq!(plain.parse::<Board>().unwrap().rows(), puzzle().rows());
+
+        let simple = "\
+379|...|.14
+.6.|.1.|.7.
+.8.|..9|..5
+-----------
+435|..7|...
+.9.|.4.|.2.
+...|8..|436
+-----------
+9..|7..|.8.
+.4.|.8.|.5.
+85.|...|249";
+        assert_eq!(simple.parse::<Board>().unwrap().rows(), puzzle().rows());
+    }
+
+    #[test]
+    fn dashes_can_be_rows_of_blanks() {
+        let board = Board::new();
+        let rows = ["---------"; 9].join("\n");
+        assert_eq!(rows.parse::<Board>().unwrap().rows(), board.rows());
+        let line = board
+            .display(DisplayStyle::Line)
+            .to_string()
+            .replace('0', "-");
+        assert_eq!(line.parse::<Board>().unwrap().rows(), board.rows());
+    }
+
+    #[test]
+    fn reports_where_parsing_failed() {
+        let grid = format!("{:#}", puzzle()).replacen('7', "x", 1);
+        assert_eq!(
+            grid.parse::<Board>().err(),
+            Some(RudokuError::UnexpectedCharacter {
+                character: 'x',
+                line: 2,
+                column: 5
+            })
+        );
+        assert_eq!(
+            "379......".parse::<Board>().err(),
+            Some(RudokuError::InvalidLength {
+                expected: 81,
+                found: 9
+            })
+        );
+    }
+
+    #[test]
+    fn puzzles_are_told_from_paths() {
+        assert!(looks_like_puzzle(
+            "379____14_6__1__7__8___9__5435__7____9__4__2____8__436_______8__4__8__5_85_____249"
+        ));
+        assert!(looks_like_puzzle("12.4|3..1\n....|....\n"));
+        assert!(!looks_like_puzzle("puzzles.sdm"));
+        assert!(!looks_like_puzzle("a.sdk"));
+    }
+}
@@ -0,0 +1,247 @@
+use std::fmt;
+
+use crate::techniques::default_techniques;
+use crate::{Board, SolveStep, Technique, TechniqueKind, Unit};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    /// The band a score falls into. Each band runs up to just below the
+    /// next rung of the technique scale, so the step count never moves a
+    /// puzzle into another band.
+    pub fn from_score(score: f32) -> Self {
+        if score < 1.6 {
+            Difficulty::Easy
+        } else if score < 2.9 {
+            Difficulty::Medium
+        } else if score < 5.5 {
+            Difficulty::Hard
+        } else {
+            Difficulty::Expert
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+            Difficulty::Expert => "Expert",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl TechniqueKind {
+    /// Rating of the technique on the Sudoku Explainer scale. Simple
+    /// colouring has no Explainer equivalent, so it sits with the short
+    /// chains, and guessing sits above everything the techniques can do.
+    pub fn score(self) -> f32 {
+        match self {
+            TechniqueKind::HiddenSingle => 1.5,
+            TechniqueKind::NakedSingle => 2.3,
+            TechniqueKind::PointingPair => 2.6,
+            TechniqueKind::BoxLineReduction => 2.8,
+            TechniqueKind::NakedPair => 3.0,
+            TechniqueKind::XWing => 3.2,
+            TechniqueKind::HiddenPair => 3.4,
+            TechniqueKind::NakedTriple => 3.6,
+            TechniqueKind::Swordfish => 3.8,
+            TechniqueKind::HiddenTriple => 4.0,
+            TechniqueKind::XyWing => 4.2,
+            TechniqueKind::XyzWing => 4.4,
+            TechniqueKind::NakedQuad => 5.0,
+            TechniqueKind::Jellyfish => 5.2,
+            TechniqueKind::HiddenQuad => 5.4,
+            TechniqueKind::SimpleColouring => 6.6,
+            TechniqueKind::Guess => 9.0,
+        }
+    }
+}
+
+/// The default techniques from the lowest score up, so that a rated solve
+/// always takes the easiest step on offer, as Sudoku Explainer does. The
+/// default order, which looks for naked singles first, would rate puzzles
+/// full of hidden singles as harder than they are.
+pub(crate) fn rating_techniques() -> Vec<Box<dyn Technique>> {
+    let mut techniques = default_techniques();
+    techniques.sort_by(|a, b| {
+        a.kind()
+            .score()
+            .partial_cmp(&b.kind().score())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    techniques
+}
+
+/// The score of a single step. As in Sudoku Explainer, a hidden single in
+/// a square is easier to spot than one in a row or column.
+pub fn step_score(step: &SolveStep) -> f32 {
+    match (step.technique, step.units.as_slice()) {
+        (TechniqueKind::HiddenSingle, [Unit::Square(_)]) => 1.2,
+        (technique, _) => technique.score(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rating {
+    /// Score of the hardest step, on the Sudoku Explainer scale, plus a
+    /// thousandth for each step so that longer solves of the same
+    /// technique rate higher. The step term stays below 0.1, the gap
+    /// between rungs of the scale.
+    pub score: f32,
+    pub difficulty: Difficulty,
+    /// The hardest technique needed, or `None` for a solved board
+    pub hardest: Option<TechniqueKind>,
+    /// Number of steps in the logical solve, including guesses
+    pub steps: usize,
+    pub guesses: usize,
+}
+
+impl Rating {
+    pub fn from_steps(steps: &[SolveStep]) -> Self {
+        let hardest = steps.iter().max_by(|a, b| {
+            step_score(a)
+                .partial_cmp(&step_score(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let score = hardest.map_or(0.0, step_score) + length_score(steps.len());
+        Self {
+            score,
+            difficulty: Difficulty::from_score(score),
+            hardest: hardest.map(|step| step.technique),
+            steps: steps.len(),
+            guesses: steps
+                .iter()
+                .filter(|step| step.technique == TechniqueKind::Guess)
+                .count(),
+        }
+    }
+}
+
+/// The share of a rating's score that comes from the number of steps
+fn length_score(steps: usize) -> f32 {
+    (steps as f32 / 1000.0).min(0.09)
+}
+
+/// The outcome of solving a board: the solution, the steps that reached it
+/// and how hard they were
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolveReport {
+    pub solution: Board,
+    pub steps: Vec<SolveStep>,
+    pub rating: Rating,
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:.2}", self.difficulty, self.score)?;
+        if let Some(hardest) = self.hardest {
+            write!(f, ", {}", hardest)?;
+        }
+        write!(f, ", {} steps)", self.steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, Sudoku};
+
+    #[test]
+    fn rate_easy_board() {
+        let board = Board::from_string(
+            "002000500010705020400090007049000730801030409036000210200080004080902060007000800",
+        )
+        .unwrap();
+        let rating = board.rate().unwrap();
+        assert_eq!(rating.difficulty, Difficulty::Easy);
+        assert_eq!(rating.guesses, 0);
+        assert!(rating.score < 1.6);
+        // Rating works on a copy
+        assert_eq!(board.get_row(0), [0, 0, 2, 0, 0, 0, 5, 0, 0]);
+    }
+
+    #[test]
+    fn rate_hard_board() {
+        let board = Board::from_string(
+            "020000000000600003074080000000003002080040010600500000000010780500009000000000040",
+        )
+        .unwrap();
+        let rating = board.rate().unwrap();
+        assert_eq!(rating.hardest, Some(TechniqueKind::XyWing));
+        assert!(rating.score > 4.2 && rating.score < 4.3);
+        assert_eq!(rating.difficulty, Difficulty::Hard);
+        assert_eq!(rating.guesses, 0);
+        assert_eq!(
+            rating.to_string(),
+            format!(
+                "Hard ({:.2}, XY-Wing, {} steps)",
+                4.2 + rating.steps as f32 / 1000.0,
+                rating.steps
+            )
+        );
+    }
+
+    #[test]
+    fn longer_solves_rate_higher() {
+        let board = Board::from_string(
+            "002000500010705020400090007049000730801030409036000210200080004080902060007000800",
+        )
+        .unwrap();
+        let mut shorter = board.clone();
+        let mut solution = board.clone();
+        solution.solve().unwrap();
+        (0..9)
+            .filter(|col_index| board.get_value(0, *col_index) == 0)
+            .for_each(|col_index| {
+                shorter
+                    .set_value(0, col_index, solution.get_value(0, col_index))
+                    .unwrap();
+            });
+
+        let long = board.rate().unwrap();
+        let short = shorter.rate().unwrap();
+        assert_eq!(long.hardest, short.hardest);
+        assert!(long.steps > short.steps);
+        assert!(long.score > short.score);
+        assert_eq!(long.difficulty, short.difficulty);
+    }
+
+    #[test]
+    fn rate_solved_board() {
+        let board = Board::from_string(
+            "845632179732918654196745328683574912457291836219863547361429785574186293928357461",
+        )
+        .unwrap();
+        let rating = board.rate().unwrap();
+        assert_eq!(rating.hardest, None);
+        assert_eq!(rating.steps, 0);
+        assert_eq!(rating.difficulty, Difficulty::Easy);
+    }
+
+    #[test]
+    fn difficulty_bands() {
+        assert_eq!(Difficulty::from_score(1.2), Difficulty::Easy);
+        assert_eq!(Difficulty::from_score(1.59), Difficulty::Easy);
+        assert_eq!(Difficulty::from_score(2.6), Difficulty::Medium);
+        assert_eq!(Difficulty::from_score(2.89), Difficulty::Medium);
+        assert_eq!(Difficulty::from_score(3.8), Difficulty::Hard);
+        assert_eq!(Difficulty::from_score(9.0), Difficulty::Expert);
+    }
+}
@@ -0,0 +1,238 @@
+//! Serde support, behind the `serde` feature. The schema is stable: fields
+//! may be added but are not renamed or removed.
+//!
+//! A board is a map of
+//!
+//! - `box_rows`, `box_cols`: the shape of its boxes
+//! - `givens`: the values set with the puzzle, as rows of numbers with 0
+//!   for any other cell
+//! - `placed`: the values filled in since, in the same layout
+//! - `candidates`: only written once techniques have removed candidates the
+//!   placed values don't rule out. Rows of lists of values, with an empty
+//!   list for each filled cell.
+//!
+//! Elsewhere, cells are `[row, col]` pairs counted from 0, placements and
+//! eliminations are `[[row, col], value]`, and units are maps such as
+//! `{"row": 0}`, `{"col": 4}` or `{"square": 8}`. Technique kinds and
+//! difficulties are written in snake case, such as `"hidden_single"`.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{bit, mask_values, Board, Shape};
+
+#[derive(Serialize, Deserialize)]
+struct BoardData {
+    box_rows: usize,
+    box_cols: usize,
+    givens: Vec<Vec<i32>>,
+    placed: Vec<Vec<i32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    candidates: Option<Vec<Vec<Vec<i32>>>>,
+}
+
+impl Board {
+    /// The values of the cells for which `keep` holds, as rows
+    fn rows_where<F>(&self, keep: F) -> Vec<Vec<i32>>
+    where
+        F: Fn(usize) -> bool,
+    {
+        self.cells
+            .chunks(self.size())
+            .enumerate()
+            .map(|(row_index, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(
+                        |(col_index, value)| match keep(row_index * self.size() + col_index) {
+                            true => *value as i32,
+                            false => 0,
+                        },
+                    )
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut refreshed = self.clone();
+        refreshed.refresh_candidates();
+        let candidates = match refreshed.candidates == self.candidates {
+            true => None,
+            false => Some(
+                self.candidates
+                    .chunks(self.size())
+                    .zip(self.cells.chunks(self.size()))
+                    .map(|(masks, values)| {
+                        masks
+                            .iter()
+                            .zip(values)
+                            .map(|(mask, value)| match value {
+                                0 => mask_values(*mask),
+                                _ => Vec::new(),
+                            })
+                            .collect()
+                    })
+                    .collect(),
+            ),
+        };
+        BoardData {
+            box_rows: self.shape.box_rows(),
+            box_cols: self.shape.box_cols(),
+            givens: self.rows_where(|index| self.givens[index]),
+            placed: self.rows_where(|index| !self.givens[index]),
+            candidates,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = BoardData::deserialize(deserializer)?;
+        let shape = Shape::new(data.box_rows, data.box_cols).map_err(D::Error::custom)?;
+        let size = shape.size();
+        let grid = |rows: &[Vec<i32>], name: &str| {
+            if rows.len() != size || rows.iter().any(|row| row.len() != size) {
+                return Err(D::Error::custom(format!(
+                    "{} must have {} rows of {} values",
+                    name, size, size
+                )));
+            }
+            rows.iter()
+                .flatten()
+                .map(|value| match (0..=size as i32).contains(value) {
+                    true => Ok(*value as u8),
+                    false => Err(D::Error::custom(format!(
+                        "{} in {} is outside 0 to {}",
+                        value, name, size
+                    ))),
+                })
+                .collect::<Result<Vec<u8>, D::Error>>()
+        };
+        let givens = grid(&data.givens, "givens")?;
+        let placed = grid(&data.placed, "placed")?;
+
+        let mut values = Vec::with_capacity(shape.cell_count());
+        for (index, (given, value)) in givens.iter().zip(&placed).enumerate() {
+            if *given > 0 && *value > 0 {
+                return Err(D::Error::custom(format!(
+                    "r{}c{} is both given and placed",
+                    index / size + 1,
+                    index % size + 1
+                )));
+            }
+            values.push(given + value);
+        }
+        let mut board = Board::from_values(shape, values).map_err(D::Error::custom)?;
+        board.givens = givens.iter().map(|value| *value > 0).collect();
+
+        if let Some(candidates) = data.candidates {
+            if candidates.len() != size || candidates.iter().any(|row| row.len() != size) {
+                return Err(D::Error::custom(format!(
+                    "candidates must have {} rows of {} lists",
+                    size, size
+                )));
+            }
+            for (index, cell) in candidates.iter().flatten().enumerate() {
+                if board.cells[index] > 0 {
+                    continue;
+                }
+                if let Some(value) = cell
+                    .iter()
+                    .find(|value| **value < 1 || **value as usize > size)
+                {
+                    return Err(D::Error::custom(format!(
+                        "candidate {} is outside 1 to {}",
+                        value, size
+                    )));
+                }
+                board.candidates[index] = cell.iter().fold(0, |mask, value| mask | bit(*value));
+            }
+        }
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::formats::{Metadata, Puzzle};
+    use crate::{Board, SolveReport, Sudoku};
+
+    const PUZZLE: &str =
+        "379000014060010070080009005435007000090040020000800436900700080040080050850000249";
+
+    fn puzzle() -> Board {
+        Board::from_string(PUZZLE).unwrap()
+    }
+
+    #[test]
+    fn givens_and_placed_are_kept_apart() {
+        let mut board = puzzle();
+        board.set_value(0, 3, 6).unwrap();
+        let json = serde_json::to_value(&board).unwrap();
+        assert_eq!(json["box_rows"], 3);
+        assert_eq!(
+            json["givens"][0],
+            serde_json::json!([3, 7, 9, 0, 0, 0, 0, 1, 4])
+        );
+        assert_eq!(
+            json["placed"][0],
+            serde_json::json!([0, 0, 0, 6, 0, 0, 0, 0, 0])
+        );
+        assert!(json.get("candidates").is_none());
+
+        let read: Board = serde_json::from_value(json).unwrap();
+        assert_eq!(read.rows(), board.rows());
+        assert_eq!(serde_json::to_value(&read).unwrap()["placed"][0][3], 6);
+    }
+
+    #[test]
+    fn candidates_are_written_after_eliminations() {
+        let mut board = puzzle();
+        board.remove_candidate((1, 0), 2);
+        let json = serde_json::to_string(&board).unwrap();
+        let read: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.cell_candidates(1, 0), vec![5]);
+        assert_eq!(read.candidates(), board.candidates());
+    }
+
+    #[test]
+    fn rejects_malformed_boards() {
+        let mut json = serde_json::to_value(puzzle()).unwrap();
+        json["placed"][0][0] = 3.into();
+        assert!(serde_json::from_value::<Board>(json.clone()).is_err());
+        json["placed"][0][0] = 0.into();
+        json["givens"][0][3] = 10.into();
+        assert!(serde_json::from_value::<Board>(json.clone()).is_err());
+        json["givens"].as_array_mut().unwrap().pop();
+        assert!(serde_json::from_value::<Board>(json).is_err());
+    }
+
+    #[test]
+    fn puzzles_and_reports_round_trip_through_toml() {
+        let board = puzzle();
+        let puzzle = Puzzle {
+            board: board.clone(),
+            metadata: Metadata {
+                id: Some("easy-1".to_string()),
+                author: Some("Jane Doe".to_string()),
+                rating: Some(board.rate().unwrap()),
+                ..Metadata::default()
+            },
+        };
+        let text = toml::to_string(&puzzle).unwrap();
+        let read: Puzzle = toml::from_str(&text).unwrap();
+        assert_eq!(read.board.rows(), board.rows());
+        assert_eq!(read.metadata, puzzle.metadata);
+
+        let report = board.report().unwrap();
+        let json = serde_json::to_string(&report).unwrap();
+        let read: SolveReport = serde_json::from_str(&json).unwrap();
+        assert!(read.solution.is_solved());
+        assert_eq!(read.steps, report.steps);
+        assert_eq!(read.rating, report.rating);
+    }
+}
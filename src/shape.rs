@@ -0,0 +1,113 @@
+use std::fmt;
+
+use crate::RudokuError;
+
+// Candidates for a cell are kept as bits of a u32
+pub(crate) const MAX_SIZE: usize = 32;
+
+/// The dimensions of the boxes on a board. A board has as many rows and
+/// columns as a box has cells, so 3x3 boxes give the standard 9x9 grid and
+/// 2x3 boxes a 6x6 one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shape {
+    box_rows: usize,
+    box_cols: usize,
+}
+
+impl Shape {
+    /// Boxes `box_rows` cells tall and `box_cols` cells wide
+    pub fn new(box_rows: usize, box_cols: usize) -> Result<Self, RudokuError> {
+        if box_rows == 0 || box_cols == 0 || box_rows * box_cols > MAX_SIZE {
+            return Err(RudokuError::InvalidShape { box_rows, box_cols });
+        }
+        Ok(Self { box_rows, box_cols })
+    }
+
+    /// The usual shape for a board with `size` rows: boxes as close to square
+    /// as possible, wider than they are tall. Sizes that only factor into
+    /// boxes one cell tall have no usual shape.
+    pub fn for_size(size: usize) -> Option<Self> {
+        let box_rows = (1..=size)
+            .take_while(|rows| rows * rows <= size)
+            .filter(|rows| size.is_multiple_of(*rows))
+            .last()?;
+        if box_rows < 2 {
+            return None;
+        }
+        Self::new(box_rows, size / box_rows).ok()
+    }
+
+    pub fn box_rows(self) -> usize {
+        self.box_rows
+    }
+
+    pub fn box_cols(self) -> usize {
+        self.box_cols
+    }
+
+    /// The number of rows, columns and boxes, and the largest value
+    pub fn size(self) -> usize {
+        self.box_rows * self.box_cols
+    }
+
+    pub fn cell_count(self) -> usize {
+        self.size() * self.size()
+    }
+
+    /// Index of the box containing a cell, in reading order
+    pub(crate) fn square_index(self, row_index: usize, col_index: usize) -> usize {
+        (row_index / self.box_rows) * (self.size() / self.box_cols) + col_index / self.box_cols
+    }
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self {
+            box_rows: 3,
+            box_cols: 3,
+        }
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.box_rows, self.box_cols)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn for_size_picks_the_usual_boxes() {
+        let shapes = [
+            (4, 2, 2),
+            (6, 2, 3),
+            (9, 3, 3),
+            (12, 3, 4),
+            (16, 4, 4),
+            (25, 5, 5),
+        ];
+        shapes.iter().for_each(|&(size, box_rows, box_cols)| {
+            assert_eq!(
+                Shape::for_size(size),
+                Some(Shape::new(box_rows, box_cols).unwrap())
+            );
+        });
+        assert_eq!(Shape::for_size(7), None);
+        assert_eq!(Shape::for_size(36), None);
+    }
+
+    #[test]
+    fn new_rejects_bad_boxes() {
+        assert_eq!(
+            Shape::new(0, 3),
+            Err(RudokuError::InvalidShape {
+                box_rows: 0,
+                box_cols: 3
+            })
+        );
+        assert!(Shape::new(6, 6).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! Interchangeable engines for solving boards outright, for when only the
+//! solutions matter and not the steps a person would take to find them
+
+use std::fmt;
+
+use crate::{Board, RudokuError, Sudoku};
+
+mod dlx;
+
+pub use dlx::DlxSolver;
+
+/// A way of finding the solutions of a board
+pub trait Solver: Send + Sync {
+    /// Count the solutions of the board, stopping once `limit` is reached
+    fn count_solutions(&self, board: &Board, limit: usize) -> usize;
+
+    /// Up to `limit` solutions of the board
+    fn solutions(&self, board: &Board, limit: usize) -> Vec<Board>;
+
+    /// A solution of the board, if it has one
+    fn solve(&self, board: &Board) -> Result<Board, RudokuError> {
+        if let Some(conflict) = board.conflicts().into_iter().next() {
+            return Err(RudokuError::DuplicateValue {
+                unit: conflict.unit,
+                value: conflict.value,
+            });
+        }
+        self.solutions(board, 1)
+            .into_iter()
+            .next()
+            .ok_or(RudokuError::Unsolvable)
+    }
+
+    fn has_unique_solution(&self, board: &Board) -> bool {
+        self.count_solutions(board, 2) == 1
+    }
+}
+
+/// Filling in singles and applying the techniques, as `Sudoku::solve` does,
+/// with a depth-first search over the candidates when they run out
+pub struct PropagationSolver;
+
+impl Solver for PropagationSolver {
+    fn count_solutions(&self, board: &Board, limit: usize) -> usize {
+        board.count_solutions(limit)
+    }
+
+    fn solutions(&self, board: &Board, limit: usize) -> Vec<Board> {
+        board.solutions(limit)
+    }
+
+    fn solve(&self, board: &Board) -> Result<Board, RudokuError> {
+        let mut solution = board.clone();
+        solution.solve()?;
+        Ok(solution)
+    }
+}
+
+/// The solvers to choose from at runtime
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Backend {
+    #[default]
+    Propagation,
+    DancingLinks,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 2] = [Backend::Propagation, Backend::DancingLinks];
+
+    pub fn solver(self) -> &'static dyn Solver {
+        match self {
+            Backend::Propagation => &PropagationSolver,
+            Backend::DancingLinks => &DlxSolver,
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Backend::Propagation => "propagation",
+            Backend::DancingLinks => "dlx",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// What one backend found for a board
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub backend: Backend,
+    /// Number of solutions, up to the limit of the check
+    pub count: usize,
+    /// Every solution as rows, in sorted order, if there are fewer than the
+    /// limit. Which solutions come first at the limit depends on the search,
+    /// so then none are kept.
+    pub solutions: Vec<Vec<Vec<i32>>>,
+}
+
+/// What every backend found for the same board, from `cross_check`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossCheck {
+    pub outcomes: Vec<Outcome>,
+}
+
+impl CrossCheck {
+    /// Whether every backend found the same solutions
+    pub fn agrees(&self) -> bool {
+        self.outcomes
+            .windows(2)
+            .all(|pair| (pair[0].count, &pair[0].solutions) == (pair[1].count, &pair[1].solutions))
+    }
+}
+
+/// Find up to `limit` solutions of the board with every backend
+pub fn cross_check(board: &Board, limit: usize) -> CrossCheck {
+    let outcomes = Backend::ALL
+        .iter()
+        .map(|backend| {
+            let mut solutions = backend
+                .solver()
+                .solutions(board, limit)
+                .iter()
+                .map(|solution| solution.rows())
+                .collect::<Vec<Vec<Vec<i32>>>>();
+            let count = solutions.len();
+            match count < limit {
+                true => solutions.sort(),
+                false => solutions.clear(),
+            }
+            Outcome {
+                backend: *backend,
+                count,
+                solutions,
+            }
+        })
+        .collect();
+    CrossCheck { outcomes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::{generate, GeneratorOptions};
+    use crate::Shape;
+
+    const PUZZLE: &str =
+        "379000014060010070080009005435007000090040020000800436900700080040080050850000249";
+    const TWO_SOLUTIONS: &str =
+        "079026814064018972281479365435267198698143527712895436923754681146982753857631249";
+
+    #[test]
+    fn backends_agree() {
+        let boards = [
+            Board::from_string(PUZZLE).unwrap(),
+            Board::from_string(&PUZZLE.replacen('0', "2", 1)).unwrap(),
+            Board::from_string(&PUZZLE.replacen('0', "3", 1)).unwrap(),
+            Board::from_string(TWO_SOLUTIONS).unwrap(),
+            generate(&GeneratorOptions {
+                seed: 4,
+                clues: 25,
+                ..GeneratorOptions::default()
+            })
+            .unwrap(),
+            Board::with_shape(Shape::new(2, 3).unwrap()),
+        ];
+        boards.iter().for_each(|board| {
+            let check = cross_check(board, 10);
+            assert!(check.agrees(), "{:?}", check);
+        });
+        assert_eq!(cross_check(&boards[1], 10).outcomes[1].count, 0);
+        assert_eq!(cross_check(&boards[3], 10).outcomes[1].count, 2);
+        assert_eq!(cross_check(&boards[5], 10).outcomes[1].count, 10);
+
+        // Every 4x4 grid
+        let check = cross_check(&Board::with_shape(Shape::new(2, 2).unwrap()), 300);
+        assert!(check.agrees());
+        assert_eq!(check.outcomes[0].count, 288);
+    }
+
+    #[test]
+    fn solve_reports_why_there_is_no_solution() {
+        let clash = Board::from_string(&PUZZLE.replacen('0', "3", 1)).unwrap();
+        let unsolvable = Board::from_string(&PUZZLE.replacen('0', "2", 1)).unwrap();
+        Backend::ALL.iter().for_each(|backend| {
+            let solver = backend.solver();
+            assert!(matches!(
+                solver.solve(&clash),
+                Err(RudokuError::DuplicateValue { .. })
+            ));
+            assert!(solver.solve(&unsolvable).is_err());
+            let solution = solver.solve(&Board::from_string(PUZZLE).unwrap()).unwrap();
+            assert!(solution.is_solved());
+            assert!(solver.has_unique_solution(&Board::from_string(PUZZLE).unwrap()));
+        });
+    }
+}
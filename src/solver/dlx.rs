@@ -0,0 +1,248 @@
+use super::Solver;
+use crate::Board;
+
+/// Knuth's Algorithm X over dancing links. Placing a value in a cell is a
+/// row of an exact cover matrix, covering four constraints: the cell is
+/// filled, and its row, column and box each hold the value once. Givens
+/// keep only the row for their value.
+pub struct DlxSolver;
+
+/// Where a value goes, as `(row, col, value)`
+type Placement = (usize, usize, i32);
+
+/// An exact cover matrix as circular doubly linked lists. Node 0 is the
+/// root, nodes 1 to the number of columns head the columns, and the rest
+/// are the ones of the matrix.
+struct Matrix {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    // Column header of each node
+    column: Vec<usize>,
+    // Index into `placements` of each node
+    row: Vec<usize>,
+    // Ones left in each column, by header
+    size: Vec<usize>,
+    placements: Vec<Placement>,
+}
+
+impl Matrix {
+    fn new(board: &Board) -> Self {
+        let shape = board.shape();
+        let size = shape.size();
+        let cells = size * size;
+        let columns = 4 * cells;
+        let mut matrix = Self {
+            left: (0..=columns)
+                .map(|node| if node == 0 { columns } else { node - 1 })
+                .collect(),
+            right: (0..=columns)
+                .map(|node| (node + 1) % (columns + 1))
+                .collect(),
+            up: (0..=columns).collect(),
+            down: (0..=columns).collect(),
+            column: (0..=columns).collect(),
+            row: vec![0; columns + 1],
+            size: vec![0; columns + 1],
+            placements: Vec::new(),
+        };
+        for row_index in 0..size {
+            for col_index in 0..size {
+                let values = match board.get_value(row_index, col_index) {
+                    0 => 1..=size as i32,
+                    given => given..=given,
+                };
+                for value in values {
+                    let digit = value as usize - 1;
+                    let square = shape.square_index(row_index, col_index);
+                    matrix.add_row(
+                        (row_index, col_index, value),
+                        [
+                            row_index * size + col_index,
+                            cells + row_index * size + digit,
+                            2 * cells + col_index * size + digit,
+                            3 * cells + square * size + digit,
+                        ],
+                    );
+                }
+            }
+        }
+        matrix
+    }
+
+    fn add_row(&mut self, placement: Placement, columns: [usize; 4]) {
+        let first = self.left.len();
+        let last = first + columns.len() - 1;
+        for (offset, column) in columns.iter().enumerate() {
+            let header = column + 1;
+            let node = first + offset;
+            self.left.push(if node == first { last } else { node - 1 });
+            self.right.push(if node == last { first } else { node + 1 });
+            // Add to the bottom of the column
+            let bottom = self.up[header];
+            self.up.push(bottom);
+            self.down.push(header);
+            self.down[bottom] = node;
+            self.up[header] = node;
+            self.column.push(header);
+            self.row.push(self.placements.len());
+            self.size[header] += 1;
+        }
+        self.placements.push(placement);
+    }
+
+    /// Remove a column, and every row with a one in it from the other
+    /// columns
+    fn cover(&mut self, header: usize) {
+        self.right[self.left[header]] = self.right[header];
+        self.left[self.right[header]] = self.left[header];
+        let mut node = self.down[header];
+        while node != header {
+            let mut other = self.right[node];
+            while other != node {
+                self.down[self.up[other]] = self.down[other];
+                self.up[self.down[other]] = self.up[other];
+                self.size[self.column[other]] -= 1;
+                other = self.right[other];
+            }
+            node = self.down[node];
+        }
+    }
+
+    /// Undo `cover`, in the reverse order
+    fn uncover(&mut self, header: usize) {
+        let mut node = self.up[header];
+        while node != header {
+            let mut other = self.left[node];
+            while other != node {
+                self.size[self.column[other]] += 1;
+                self.down[self.up[other]] = other;
+                self.up[self.down[other]] = other;
+                other = self.left[other];
+            }
+            node = self.up[node];
+        }
+        self.right[self.left[header]] = header;
+        self.left[self.right[header]] = header;
+    }
+
+    /// Pass each exact cover to `found` as indexes into `placements`, until
+    /// it returns `true` to stop. Returns whether the search was stopped.
+    fn search<F>(&mut self, chosen: &mut Vec<usize>, found: &mut F) -> bool
+    where
+        F: FnMut(&[usize]) -> bool,
+    {
+        if self.right[0] == 0 {
+            return found(chosen);
+        }
+        // Branch on the column with the fewest ones
+        let mut header = self.right[0];
+        let mut best = header;
+        while header != 0 {
+            if self.size[header] < self.size[best] {
+                best = header;
+            }
+            header = self.right[header];
+        }
+        if self.size[best] == 0 {
+            return false;
+        }
+        self.cover(best);
+        let mut stopped = false;
+        let mut node = self.down[best];
+        while node != best && !stopped {
+            chosen.push(self.row[node]);
+            let mut other = self.right[node];
+            while other != node {
+                self.cover(self.column[other]);
+                other = self.right[other];
+            }
+            stopped = self.search(chosen, found);
+            let mut other = self.left[node];
+            while other != node {
+                self.uncover(self.column[other]);
+                other = self.left[other];
+            }
+            chosen.pop();
+            node = self.down[node];
+        }
+        self.uncover(best);
+        stopped
+    }
+}
+
+impl Solver for DlxSolver {
+    fn count_solutions(&self, board: &Board, limit: usize) -> usize {
+        let mut count = 0;
+        if limit > 0 {
+            Matrix::new(board).search(&mut Vec::new(), &mut |_| {
+                count += 1;
+                count >= limit
+            });
+        }
+        count
+    }
+
+    fn solutions(&self, board: &Board, limit: usize) -> Vec<Board> {
+        let mut found = Vec::new();
+        if limit == 0 {
+            return found;
+        }
+        let mut matrix = Matrix::new(board);
+        let placements = matrix.placements.clone();
+        matrix.search(&mut Vec::new(), &mut |chosen| {
+            let mut solution = board.clone();
+            chosen.iter().for_each(|placement| {
+                let (row_index, col_index, value) = placements[*placement];
+                if solution.get_value(row_index, col_index) == 0 {
+                    solution.fill_cell(row_index, col_index, value);
+                }
+            });
+            solution.refresh_candidates();
+            found.push(solution);
+            found.len() >= limit
+        });
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate::complete_grid;
+    use crate::{Shape, Sudoku};
+
+    #[test]
+    fn solves_every_shape() {
+        [(2, 3), (3, 3), (3, 4), (4, 4)]
+            .iter()
+            .for_each(|&(rows, cols)| {
+                let grid = complete_grid(Shape::new(rows, cols).unwrap(), 1);
+                // Keep every other cell in reading order
+                let mut board = Board::with_shape(grid.shape());
+                (0..grid.size()).for_each(|i| {
+                    (0..grid.size())
+                        .filter(|j| (i + j) % 2 == 0)
+                        .for_each(|j| board.fill_cell(i, j, grid.get_value(i, j)));
+                });
+                board.refresh_candidates();
+                let solution = DlxSolver.solve(&board).unwrap();
+                assert!(solution.is_solved());
+                assert!(solution.board_valid());
+                (0..grid.size()).for_each(|i| {
+                    (0..grid.size())
+                        .filter(|j| (i + j) % 2 == 0)
+                        .for_each(|j| assert_eq!(solution.get_value(i, j), grid.get_value(i, j)));
+                });
+            });
+    }
+
+    #[test]
+    fn counts_stop_at_the_limit() {
+        let board = Board::new();
+        assert_eq!(DlxSolver.count_solutions(&board, 0), 0);
+        assert_eq!(DlxSolver.count_solutions(&board, 50), 50);
+        assert_eq!(DlxSolver.solutions(&board, 3).len(), 3);
+    }
+}
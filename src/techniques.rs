@@ -0,0 +1,686 @@
+use std::fmt;
+
+use crate::{Board, Unit};
+
+mod colouring;
+mod fish;
+mod wings;
+
+pub use colouring::SimpleColouring;
+pub use fish::Fish;
+pub use wings::{XyWing, XyzWing};
+
+/// The human-style solving techniques, in rough order of difficulty
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TechniqueKind {
+    NakedSingle,
+    HiddenSingle,
+    PointingPair,
+    BoxLineReduction,
+    NakedPair,
+    XWing,
+    HiddenPair,
+    NakedTriple,
+    Swordfish,
+    HiddenTriple,
+    XyWing,
+    XyzWing,
+    NakedQuad,
+    Jellyfish,
+    HiddenQuad,
+    SimpleColouring,
+    /// Not a technique: a value taken from the solution once logic runs out
+    Guess,
+}
+
+impl fmt::Display for TechniqueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TechniqueKind::NakedSingle => "Naked single",
+            TechniqueKind::HiddenSingle => "Hidden single",
+            TechniqueKind::PointingPair => "Pointing pair",
+            TechniqueKind::BoxLineReduction => "Box/line reduction",
+            TechniqueKind::NakedPair => "Naked pair",
+            TechniqueKind::XWing => "X-Wing",
+            TechniqueKind::HiddenPair => "Hidden pair",
+            TechniqueKind::NakedTriple => "Naked triple",
+            TechniqueKind::Swordfish => "Swordfish",
+            TechniqueKind::HiddenTriple => "Hidden triple",
+            TechniqueKind::XyWing => "XY-Wing",
+            TechniqueKind::XyzWing => "XYZ-Wing",
+            TechniqueKind::NakedQuad => "Naked quad",
+            TechniqueKind::Jellyfish => "Jellyfish",
+            TechniqueKind::HiddenQuad => "Hidden quad",
+            TechniqueKind::SimpleColouring => "Simple colouring",
+            TechniqueKind::Guess => "Guess",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One move of a solve: values to place and candidates to remove, as
+/// `((row, col), value)` pairs, along with the cells, candidate values and
+/// units making up the pattern that justifies them
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolveStep {
+    pub technique: TechniqueKind,
+    pub placements: Vec<((usize, usize), i32)>,
+    pub eliminations: Vec<((usize, usize), i32)>,
+    pub cells: Vec<(usize, usize)>,
+    pub values: Vec<i32>,
+    pub units: Vec<Unit>,
+}
+
+impl SolveStep {
+    pub(crate) fn placement(technique: TechniqueKind, cell: (usize, usize), value: i32) -> Self {
+        Self {
+            technique,
+            placements: vec![(cell, value)],
+            eliminations: vec![],
+            cells: vec![cell],
+            values: vec![value],
+            units: vec![],
+        }
+    }
+
+    fn eliminations(
+        technique: TechniqueKind,
+        cells: Vec<(usize, usize)>,
+        mut values: Vec<i32>,
+        mut eliminations: Vec<((usize, usize), i32)>,
+    ) -> Option<Self> {
+        eliminations.sort();
+        eliminations.dedup();
+        if eliminations.is_empty() {
+            return None;
+        }
+        values.sort();
+        values.dedup();
+        Some(Self {
+            technique,
+            placements: vec![],
+            eliminations,
+            cells,
+            values,
+            units: vec![],
+        })
+    }
+
+    fn with_units(mut self, units: Vec<Unit>) -> Self {
+        self.units = units;
+        self
+    }
+}
+
+/// A cell in the usual `r1c1` notation, counting from 1
+pub(crate) fn cell_name(cell: (usize, usize)) -> String {
+    format!("r{}c{}", cell.0 + 1, cell.1 + 1)
+}
+
+fn join<T: ToString>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+pub(crate) fn join_cells(cells: &[(usize, usize)]) -> String {
+    join(
+        &cells
+            .iter()
+            .map(|cell| cell_name(*cell))
+            .collect::<Vec<String>>(),
+    )
+}
+
+impl fmt::Display for SolveStep {
+    /// A human-readable explanation of the step
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.technique)?;
+        if let [(cell, value)] = self.placements.as_slice() {
+            return match (self.technique, self.units.as_slice()) {
+                (TechniqueKind::HiddenSingle, [unit]) => write!(
+                    f,
+                    "{} in {} can only go in {}",
+                    value,
+                    unit,
+                    cell_name(*cell)
+                ),
+                (TechniqueKind::Guess, _) => {
+                    write!(f, "try {} in {}", value, cell_name(*cell))
+                }
+                _ => write!(f, "{} can only be {}", cell_name(*cell), value),
+            };
+        }
+        let values = join(&self.values);
+        match (self.technique, self.units.as_slice()) {
+            (TechniqueKind::PointingPair, [from, to])
+            | (TechniqueKind::BoxLineReduction, [from, to]) => {
+                write!(f, "{} in {} is confined to {}", values, from, to)?
+            }
+            (TechniqueKind::NakedPair, [unit])
+            | (TechniqueKind::NakedTriple, [unit])
+            | (TechniqueKind::NakedQuad, [unit]) => write!(
+                f,
+                "{} only hold {} in {}",
+                join_cells(&self.cells),
+                values,
+                unit
+            )?,
+            (TechniqueKind::HiddenPair, [unit])
+            | (TechniqueKind::HiddenTriple, [unit])
+            | (TechniqueKind::HiddenQuad, [unit]) => write!(
+                f,
+                "{} in {} can only go in {}",
+                values,
+                unit,
+                join_cells(&self.cells)
+            )?,
+            (TechniqueKind::XWing, units)
+            | (TechniqueKind::Swordfish, units)
+            | (TechniqueKind::Jellyfish, units) => {
+                let (base, cover) = units.split_at(units.len() / 2);
+                write!(
+                    f,
+                    "{} in {} is confined to {}",
+                    values,
+                    join(base),
+                    join(cover)
+                )?
+            }
+            (TechniqueKind::XyWing, _) | (TechniqueKind::XyzWing, _) => {
+                let (pivot, pincers) = self.cells.split_first().unwrap_or((&(0, 0), &[]));
+                write!(
+                    f,
+                    "pivot {} with pincers {} on {}",
+                    cell_name(*pivot),
+                    join_cells(pincers),
+                    values
+                )?
+            }
+            _ => write!(f, "{} through {}", values, join_cells(&self.cells))?,
+        }
+        let mut removed_values = self
+            .eliminations
+            .iter()
+            .map(|(_, value)| *value)
+            .collect::<Vec<i32>>();
+        removed_values.sort();
+        removed_values.dedup();
+        if let [value] = removed_values.as_slice() {
+            let cells = self
+                .eliminations
+                .iter()
+                .map(|(cell, _)| *cell)
+                .collect::<Vec<(usize, usize)>>();
+            return write!(f, ", so remove {} from {}", value, join_cells(&cells));
+        }
+        // Eliminations are sorted by cell, so group the values per cell
+        let mut removed: Vec<((usize, usize), Vec<i32>)> = Vec::new();
+        self.eliminations
+            .iter()
+            .for_each(|(cell, value)| match removed.last_mut() {
+                Some((last, values)) if last == cell => values.push(*value),
+                _ => removed.push((*cell, vec![*value])),
+            });
+        let removed = removed
+            .iter()
+            .map(|(cell, values)| format!("{} from {}", join(values), cell_name(*cell)))
+            .collect::<Vec<String>>();
+        write!(f, ", so remove {}", removed.join(" and "))
+    }
+}
+
+/// A logical solving technique working on a board's candidate grid
+pub trait Technique {
+    fn kind(&self) -> TechniqueKind;
+    /// The first step the technique makes on the board, if any
+    fn find(&self, board: &Board) -> Option<SolveStep>;
+}
+
+/// Every technique, simplest first
+pub fn default_techniques() -> Vec<Box<dyn Technique>> {
+    vec![
+        Box::new(NakedSingle),
+        Box::new(HiddenSingle),
+        Box::new(PointingPair),
+        Box::new(BoxLineReduction),
+        Box::new(NakedSubset { size: 2 }),
+        Box::new(Fish { size: 2 }),
+        Box::new(HiddenSubset { size: 2 }),
+        Box::new(NakedSubset { size: 3 }),
+        Box::new(Fish { size: 3 }),
+        Box::new(HiddenSubset { size: 3 }),
+        Box::new(XyWing),
+        Box::new(XyzWing),
+        Box::new(NakedSubset { size: 4 }),
+        Box::new(Fish { size: 4 }),
+        Box::new(HiddenSubset { size: 4 }),
+        Box::new(SimpleColouring),
+    ]
+}
+
+/// A cell with only one candidate left
+pub struct NakedSingle;
+
+/// A value that fits in only one cell of a unit
+pub struct HiddenSingle;
+
+/// A value confined to one row or column within a square, so it can be
+/// removed from the rest of that row or column
+pub struct PointingPair;
+
+/// A value confined to one square within a row or column, so it can be
+/// removed from the rest of that square
+pub struct BoxLineReduction;
+
+/// `size` cells in a unit holding only `size` candidates between them
+pub struct NakedSubset {
+    pub size: usize,
+}
+
+/// `size` values in a unit that fit only in the same `size` cells
+pub struct HiddenSubset {
+    pub size: usize,
+}
+
+fn empty_cells(board: &Board, unit: Unit) -> Vec<(usize, usize)> {
+    unit.cells(board.shape())
+        .into_iter()
+        .filter(|&(i, j)| board.get_value(i, j) == 0)
+        .collect()
+}
+
+fn candidates_of(board: &Board, cell: (usize, usize)) -> Vec<i32> {
+    board.cell_candidates(cell.0, cell.1)
+}
+
+/// The empty cells in a unit that still have `value` as a candidate
+fn cells_with(board: &Board, unit: Unit, value: i32) -> Vec<(usize, usize)> {
+    empty_cells(board, unit)
+        .into_iter()
+        .filter(|cell| candidates_of(board, *cell).contains(&value))
+        .collect()
+}
+
+/// Whether two distinct cells share a row, column or square
+fn sees(board: &Board, a: (usize, usize), b: (usize, usize)) -> bool {
+    a != b
+        && (a.0 == b.0
+            || a.1 == b.1
+            || Unit::square_of(board.shape(), a.0, a.1).contains(board.shape(), b.0, b.1))
+}
+
+/// The values that can go on the board
+fn values(board: &Board) -> std::ops::RangeInclusive<i32> {
+    1..=board.size() as i32
+}
+
+/// Every empty cell on the board, in reading order
+fn all_empty_cells(board: &Board) -> Vec<(usize, usize)> {
+    Unit::all(board.shape())
+        .into_iter()
+        .filter(|unit| matches!(unit, Unit::Row(_)))
+        .flat_map(|unit| empty_cells(board, unit))
+        .collect()
+}
+
+fn combinations<T: Copy>(items: &[T], size: usize) -> Vec<Vec<T>> {
+    if size == 0 {
+        return vec![vec![]];
+    }
+    if items.len() < size {
+        return vec![];
+    }
+    let mut with_first = combinations(&items[1..], size - 1);
+    with_first
+        .iter_mut()
+        .for_each(|combination| combination.insert(0, items[0]));
+    with_first.extend(combinations(&items[1..], size));
+    with_first
+}
+
+impl Technique for NakedSingle {
+    fn kind(&self) -> TechniqueKind {
+        TechniqueKind::NakedSingle
+    }
+
+    fn find(&self, board: &Board) -> Option<SolveStep> {
+        all_empty_cells(board)
+            .into_iter()
+            .find(|cell| candidates_of(board, *cell).len() == 1)
+            .map(|cell| {
+                SolveStep::placement(self.kind(), cell, candidates_of(board, cell)[0]).with_units(
+                    vec![
+                        Unit::Row(cell.0),
+                        Unit::Col(cell.1),
+                        Unit::square_of(board.shape(), cell.0, cell.1),
+                    ],
+                )
+            })
+    }
+}
+
+impl Technique for HiddenSingle {
+    fn kind(&self) -> TechniqueKind {
+        TechniqueKind::HiddenSingle
+    }
+
+    fn find(&self, board: &Board) -> Option<SolveStep> {
+        Unit::all(board.shape()).into_iter().find_map(|unit| {
+            values(board).find_map(|value| match cells_with(board, unit, value).as_slice() {
+                [cell] => {
+                    Some(SolveStep::placement(self.kind(), *cell, value).with_units(vec![unit]))
+                }
+                _ => None,
+            })
+        })
+    }
+}
+
+impl Technique for PointingPair {
+    fn kind(&self) -> TechniqueKind {
+        TechniqueKind::PointingPair
+    }
+
+    fn find(&self, board: &Board) -> Option<SolveStep> {
+        Unit::all(board.shape())
+            .into_iter()
+            .filter(|unit| matches!(unit, Unit::Square(_)))
+            .find_map(|square| {
+                values(board).find_map(|value| {
+                    let cells = cells_with(board, square, value);
+                    if cells.len() < 2 {
+                        return None;
+                    }
+                    let (row_index, col_index) = cells[0];
+                    [Unit::Row(row_index), Unit::Col(col_index)]
+                        .iter()
+                        .filter(|line| {
+                            cells
+                                .iter()
+                                .all(|&(i, j)| line.contains(board.shape(), i, j))
+                        })
+                        .find_map(|line| {
+                            let eliminations = cells_with(board, *line, value)
+                                .into_iter()
+                                .filter(|&(i, j)| !square.contains(board.shape(), i, j))
+                                .map(|cell| (cell, value))
+                                .collect();
+                            SolveStep::eliminations(
+                                self.kind(),
+                                cells.clone(),
+                                vec![value],
+                                eliminations,
+                            )
+                            .map(|step| step.with_units(vec![square, *line]))
+                        })
+                })
+            })
+    }
+}
+
+impl Technique for BoxLineReduction {
+    fn kind(&self) -> TechniqueKind {
+        TechniqueKind::BoxLineReduction
+    }
+
+    fn find(&self, board: &Board) -> Option<SolveStep> {
+        Unit::all(board.shape())
+            .into_iter()
+            .filter(|unit| !matches!(unit, Unit::Square(_)))
+            .find_map(|line| {
+                values(board).find_map(|value| {
+                    let cells = cells_with(board, line, value);
+                    if cells.len() < 2 {
+                        return None;
+                    }
+                    let square = Unit::square_of(board.shape(), cells[0].0, cells[0].1);
+                    if !cells
+                        .iter()
+                        .all(|&(i, j)| square.contains(board.shape(), i, j))
+                    {
+                        return None;
+                    }
+                    let eliminations = cells_with(board, square, value)
+                        .into_iter()
+                        .filter(|&(i, j)| !line.contains(board.shape(), i, j))
+                        .map(|cell| (cell, value))
+                        .collect();
+                    SolveStep::eliminations(self.kind(), cells, vec![value], eliminations)
+                        .map(|step| step.with_units(vec![line, square]))
+                })
+            })
+    }
+}
+
+impl Technique for NakedSubset {
+    fn kind(&self) -> TechniqueKind {
+        match self.size {
+            2 => TechniqueKind::NakedPair,
+            3 => TechniqueKind::NakedTriple,
+            _ => TechniqueKind::NakedQuad,
+        }
+    }
+
+    fn find(&self, board: &Board) -> Option<SolveStep> {
+        Unit::all(board.shape()).into_iter().find_map(|unit| {
+            let empty = empty_cells(board, unit);
+            let small = empty
+                .iter()
+                .copied()
+                .filter(|cell| {
+                    let count = candidates_of(board, *cell).len();
+                    count >= 2 && count <= self.size
+                })
+                .collect::<Vec<(usize, usize)>>();
+            combinations(&small, self.size)
+                .into_iter()
+                .find_map(|subset| {
+                    let mut values = subset
+                        .iter()
+                        .flat_map(|cell| candidates_of(board, *cell))
+                        .collect::<Vec<i32>>();
+                    values.sort();
+                    values.dedup();
+                    if values.len() != self.size {
+                        return None;
+                    }
+                    let eliminations = empty
+                        .iter()
+                        .filter(|cell| !subset.contains(cell))
+                        .flat_map(|cell| {
+                            candidates_of(board, *cell)
+                                .into_iter()
+                                .filter(|value| values.contains(value))
+                                .map(move |value| (*cell, value))
+                        })
+                        .collect();
+                    SolveStep::eliminations(self.kind(), subset, values, eliminations)
+                        .map(|step| step.with_units(vec![unit]))
+                })
+        })
+    }
+}
+
+impl Technique for HiddenSubset {
+    fn kind(&self) -> TechniqueKind {
+        match self.size {
+            2 => TechniqueKind::HiddenPair,
+            3 => TechniqueKind::HiddenTriple,
+            _ => TechniqueKind::HiddenQuad,
+        }
+    }
+
+    fn find(&self, board: &Board) -> Option<SolveStep> {
+        Unit::all(board.shape()).into_iter().find_map(|unit| {
+            let values = values(board)
+                .filter(|value| {
+                    let count = cells_with(board, unit, *value).len();
+                    count >= 2 && count <= self.size
+                })
+                .collect::<Vec<i32>>();
+            combinations(&values, self.size)
+                .into_iter()
+                .find_map(|subset| {
+                    let mut cells = subset
+                        .iter()
+                        .flat_map(|value| cells_with(board, unit, *value))
+                        .collect::<Vec<(usize, usize)>>();
+                    cells.sort();
+                    cells.dedup();
+                    if cells.len() != self.size {
+                        return None;
+                    }
+                    let eliminations = cells
+                        .iter()
+                        .flat_map(|cell| {
+                            candidates_of(board, *cell)
+                                .into_iter()
+                                .filter(|value| !subset.contains(value))
+                                .map(move |value| (*cell, value))
+                        })
+                        .collect();
+                    SolveStep::eliminations(self.kind(), cells, subset, eliminations)
+                        .map(|step| step.with_units(vec![unit]))
+                })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sudoku;
+
+    #[test]
+    fn naked_single() {
+        let board = Board::from_string(
+            "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
+        )
+        .unwrap();
+        assert_eq!(
+            NakedSingle.find(&board),
+            Some(
+                SolveStep::placement(TechniqueKind::NakedSingle, (2, 7), 6).with_units(vec![
+                    Unit::Row(2),
+                    Unit::Col(7),
+                    Unit::Square(2)
+                ])
+            )
+        );
+    }
+
+    #[test]
+    fn hidden_single() {
+        let board = Board::from_string(
+            "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
+        )
+        .unwrap();
+        let step = HiddenSingle.find(&board).unwrap();
+        assert_eq!(step.technique, TechniqueKind::HiddenSingle);
+        assert_eq!(step.placements.len(), 1);
+        assert!(step.eliminations.is_empty());
+    }
+
+    #[test]
+    fn naked_pair() {
+        let mut board = Board::new();
+        board.set_candidates((0, 0), &[1, 2]);
+        board.set_candidates((0, 1), &[1, 2]);
+        let step = NakedSubset { size: 2 }.find(&board).unwrap();
+        assert_eq!(step.technique, TechniqueKind::NakedPair);
+        assert_eq!(
+            step.eliminations,
+            (2..9)
+                .flat_map(|j| vec![((0, j), 1), ((0, j), 2)])
+                .collect::<Vec<((usize, usize), i32)>>()
+        );
+    }
+
+    #[test]
+    fn pointing_pair() {
+        // In square 0, 1 can only go in row 0, so it leaves the rest of row 0
+        let mut board = Board::new();
+        [
+            (1, 0, 2),
+            (1, 1, 3),
+            (1, 2, 4),
+            (2, 0, 5),
+            (2, 1, 6),
+            (2, 2, 7),
+        ]
+        .iter()
+        .for_each(|&(i, j, value)| board.set_value(i, j, value).unwrap());
+        let step = PointingPair.find(&board).unwrap();
+        assert_eq!(step.technique, TechniqueKind::PointingPair);
+        assert_eq!(
+            step.to_string(),
+            "Pointing pair: 1 in square 1 is confined to row 1, \
+             so remove 1 from r1c4, r1c5, r1c6, r1c7, r1c8, r1c9"
+        );
+        assert_eq!(
+            step.eliminations,
+            (3..9)
+                .map(|j| ((0, j), 1))
+                .collect::<Vec<((usize, usize), i32)>>()
+        );
+    }
+
+    #[test]
+    fn box_line_reduction() {
+        // In row 0, 1 can only go in square 0, so it leaves the rest of square 0
+        let mut board = Board::new();
+        (3..9).for_each(|j| board.set_value(0, j, j as i32 - 1).unwrap());
+        let step = BoxLineReduction.find(&board).unwrap();
+        assert_eq!(step.technique, TechniqueKind::BoxLineReduction);
+        assert_eq!(
+            step.eliminations,
+            [(1, 0), (1, 1), (1, 2), (2, 0), (2, 1), (2, 2)]
+                .iter()
+                .map(|cell| (*cell, 1))
+                .collect::<Vec<((usize, usize), i32)>>()
+        );
+    }
+
+    #[test]
+    fn hidden_pair() {
+        let mut board = Board::new();
+        (2..9).for_each(|j| {
+            board.set_candidates((0, j), &[3, 4, 5, 6, 7, 8, 9]);
+        });
+        let step = HiddenSubset { size: 2 }.find(&board).unwrap();
+        assert_eq!(step.technique, TechniqueKind::HiddenPair);
+        assert_eq!(
+            step.to_string(),
+            "Hidden pair: 1, 2 in row 1 can only go in r1c1, r1c2, \
+             so remove 3, 4, 5, 6, 7, 8, 9 from r1c1 and 3, 4, 5, 6, 7, 8, 9 from r1c2"
+        );
+        assert_eq!(
+            step.eliminations,
+            [(0, 0), (0, 1)]
+                .iter()
+                .flat_map(|cell| (3..=9).map(move |value| (*cell, value)))
+                .collect::<Vec<((usize, usize), i32)>>()
+        );
+    }
+
+    #[test]
+    fn techniques_solve_without_guessing() {
+        // Naked singles alone stall on this board
+        let mut board = Board::from_string(
+            "379000014060010070080009005435007000090040020000800436900700080040080050850000249",
+        )
+        .unwrap();
+        while board.solve_step().is_some() {}
+        assert!(board.board_valid());
+        assert!(board.board_complete());
+    }
+}
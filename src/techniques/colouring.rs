@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use super::{
+    all_empty_cells, candidates_of, cells_with, sees, values, SolveStep, Technique, TechniqueKind,
+};
+use crate::{Board, Unit};
+
+/// Chains of conjugate pairs for a single value, coloured alternately. Two
+/// cells of one colour in the same unit rule that colour out, and a cell
+/// seeing both colours can't hold the value.
+pub struct SimpleColouring;
+
+impl SimpleColouring {
+    fn find_for_value(&self, board: &Board, value: i32) -> Option<SolveStep> {
+        let mut links: HashMap<(usize, usize), Vec<(usize, usize)>> = HashMap::new();
+        Unit::all(board.shape()).into_iter().for_each(|unit| {
+            if let [a, b] = cells_with(board, unit, value).as_slice() {
+                links.entry(*a).or_default().push(*b);
+                links.entry(*b).or_default().push(*a);
+            }
+        });
+        let cells = all_empty_cells(board)
+            .into_iter()
+            .filter(|cell| candidates_of(board, *cell).contains(&value))
+            .collect::<Vec<(usize, usize)>>();
+
+        let mut coloured: HashMap<(usize, usize), bool> = HashMap::new();
+        cells.iter().find_map(|start| {
+            if coloured.contains_key(start) || !links.contains_key(start) {
+                return None;
+            }
+            // Colour the chain containing `start`
+            let mut chain = vec![(*start, true)];
+            let mut stack = vec![(*start, true)];
+            coloured.insert(*start, true);
+            while let Some((cell, colour)) = stack.pop() {
+                links[&cell].iter().for_each(|next| {
+                    if !coloured.contains_key(next) {
+                        coloured.insert(*next, !colour);
+                        chain.push((*next, !colour));
+                        stack.push((*next, !colour));
+                    }
+                });
+            }
+            let colour_cells = |colour: bool| {
+                chain
+                    .iter()
+                    .filter(|(_, c)| *c == colour)
+                    .map(|(cell, _)| *cell)
+                    .collect::<Vec<(usize, usize)>>()
+            };
+            let (on, off) = (colour_cells(true), colour_cells(false));
+
+            // Colour wrap: a colour seeing itself is false everywhere
+            let wrap = [&on, &off].iter().find_map(|group| {
+                let clashes = group
+                    .iter()
+                    .any(|a| group.iter().any(|b| sees(board, *a, *b)));
+                if clashes {
+                    Some(group.iter().map(|cell| (*cell, value)).collect())
+                } else {
+                    None
+                }
+            });
+            // Colour trap: an uncoloured cell seeing both colours
+            let eliminations = wrap.unwrap_or_else(|| {
+                cells
+                    .iter()
+                    .filter(|cell| !chain.iter().any(|(c, _)| c == *cell))
+                    .filter(|cell| {
+                        on.iter().any(|a| sees(board, **cell, *a))
+                            && off.iter().any(|b| sees(board, **cell, *b))
+                    })
+                    .map(|cell| (*cell, value))
+                    .collect()
+            });
+            let mut chain_cells = chain.iter().map(|(cell, _)| *cell).collect::<Vec<_>>();
+            chain_cells.sort();
+            SolveStep::eliminations(self.kind(), chain_cells, vec![value], eliminations)
+        })
+    }
+}
+
+impl Technique for SimpleColouring {
+    fn kind(&self) -> TechniqueKind {
+        TechniqueKind::SimpleColouring
+    }
+
+    fn find(&self, board: &Board) -> Option<SolveStep> {
+        values(board).find_map(|value| self.find_for_value(board, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A blank board where `value` is only a candidate in `cells`
+    fn board_with_value_in(value: i32, cells: &[(usize, usize)]) -> Board {
+        let mut board = Board::new();
+        (0..9).for_each(|i| {
+            (0..9)
+                .filter(|j| !cells.contains(&(i, *j)))
+                .for_each(|j| board.remove_candidate((i, j), value));
+        });
+        board
+    }
+
+    #[test]
+    fn colour_trap() {
+        // (0, 0) -> (0, 4) -> (2, 5) -> (6, 5) alternate colours, and (6, 0)
+        // sees both ends
+        let board = board_with_value_in(
+            1,
+            &[
+                (0, 0),
+                (0, 4),
+                (2, 5),
+                (6, 5),
+                (6, 0),
+                (8, 0),
+                (7, 1),
+                (6, 8),
+            ],
+        );
+        let step = SimpleColouring.find(&board).unwrap();
+        assert_eq!(step.technique, TechniqueKind::SimpleColouring);
+        assert_eq!(step.cells, vec![(0, 0), (0, 4), (2, 5), (6, 5)]);
+        assert_eq!(step.eliminations, vec![((6, 0), 1)]);
+    }
+
+    #[test]
+    fn colour_wrap() {
+        // (0, 0) -> (0, 4) -> (3, 4) -> (3, 1) -> (1, 1) puts (0, 0) and
+        // (1, 1) in square 0 with the same colour
+        let board = board_with_value_in(2, &[(0, 0), (0, 4), (3, 4), (3, 1), (1, 1), (2, 2)]);
+        let step = SimpleColouring.find(&board).unwrap();
+        assert_eq!(step.cells, vec![(0, 0), (0, 4), (1, 1), (3, 1), (3, 4)]);
+        assert_eq!(
+            step.eliminations,
+            vec![((0, 0), 2), ((1, 1), 2), ((3, 4), 2)]
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use super::{cells_with, combinations, values, SolveStep, Technique, TechniqueKind};
+use crate::{Board, Unit};
+
+/// A value confined to the same `size` columns within `size` rows (or the
+/// other way round), so it can be removed from the rest of those columns:
+/// X-Wing for 2, Swordfish for 3 and Jellyfish for 4
+pub struct Fish {
+    pub size: usize,
+}
+
+impl Fish {
+    fn find_in_lines(
+        &self,
+        board: &Board,
+        value: i32,
+        base: fn(usize) -> Unit,
+        cover: fn(usize) -> Unit,
+    ) -> Option<SolveStep> {
+        // Position of a cell along its base line, which is its cover line
+        let position = |cell: (usize, usize)| match base(0) {
+            Unit::Row(_) => cell.1,
+            _ => cell.0,
+        };
+        let lines = (0..board.size())
+            .filter(|index| {
+                let count = cells_with(board, base(*index), value).len();
+                count >= 2 && count <= self.size
+            })
+            .collect::<Vec<usize>>();
+        combinations(&lines, self.size)
+            .into_iter()
+            .find_map(|base_lines| {
+                let cells = base_lines
+                    .iter()
+                    .flat_map(|index| cells_with(board, base(*index), value))
+                    .collect::<Vec<(usize, usize)>>();
+                let mut cover_lines = cells
+                    .iter()
+                    .map(|cell| position(*cell))
+                    .collect::<Vec<usize>>();
+                cover_lines.sort();
+                cover_lines.dedup();
+                if cover_lines.len() != self.size {
+                    return None;
+                }
+                let eliminations = cover_lines
+                    .iter()
+                    .flat_map(|index| cells_with(board, cover(*index), value))
+                    .filter(|cell| !cells.contains(cell))
+                    .map(|cell| (cell, value))
+                    .collect();
+                let units = base_lines
+                    .iter()
+                    .map(|index| base(*index))
+                    .chain(cover_lines.iter().map(|index| cover(*index)))
+                    .collect();
+                SolveStep::eliminations(self.kind(), cells, vec![value], eliminations)
+                    .map(|step| step.with_units(units))
+            })
+    }
+}
+
+impl Technique for Fish {
+    fn kind(&self) -> TechniqueKind {
+        match self.size {
+            2 => TechniqueKind::XWing,
+            3 => TechniqueKind::Swordfish,
+            _ => TechniqueKind::Jellyfish,
+        }
+    }
+
+    fn find(&self, board: &Board) -> Option<SolveStep> {
+        values(board).find_map(|value| {
+            self.find_in_lines(board, value, Unit::Row, Unit::Col)
+                .or_else(|| self.find_in_lines(board, value, Unit::Col, Unit::Row))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A blank board where `value` is only a candidate in `cells` within the
+    /// given rows
+    fn board_with_rows(value: i32, rows: &[usize], cells: &[(usize, usize)]) -> Board {
+        let mut board = Board::new();
+        rows.iter().for_each(|&i| {
+            (0..9)
+                .filter(|j| !cells.contains(&(i, *j)))
+                .for_each(|j| board.remove_candidate((i, j), value));
+        });
+        board
+    }
+
+    fn eliminated_from_cols(
+        value: i32,
+        rows: &[usize],
+        cols: &[usize],
+    ) -> Vec<((usize, usize), i32)> {
+        let mut eliminations = (0..9)
+            .filter(|i| !rows.contains(i))
+            .flat_map(|i| cols.iter().map(move |j| ((i, *j), value)))
+            .collect::<Vec<((usize, usize), i32)>>();
+        eliminations.sort();
+        eliminations
+    }
+
+    #[test]
+    fn x_wing() {
+        let board = board_with_rows(1, &[0, 4], &[(0, 2), (0, 6), (4, 2), (4, 6)]);
+        let step = Fish { size: 2 }.find(&board).unwrap();
+        assert_eq!(step.technique, TechniqueKind::XWing);
+        assert_eq!(step.cells, vec![(0, 2), (0, 6), (4, 2), (4, 6)]);
+        assert_eq!(step.values, vec![1]);
+        assert_eq!(step.eliminations, eliminated_from_cols(1, &[0, 4], &[2, 6]));
+    }
+
+    #[test]
+    fn swordfish() {
+        let board = board_with_rows(
+            5,
+            &[0, 3, 6],
+            &[(0, 1), (0, 4), (3, 4), (3, 7), (6, 1), (6, 7)],
+        );
+        assert_eq!(Fish { size: 2 }.find(&board), None);
+        let step = Fish { size: 3 }.find(&board).unwrap();
+        assert_eq!(step.technique, TechniqueKind::Swordfish);
+        assert_eq!(
+            step.eliminations,
+            eliminated_from_cols(5, &[0, 3, 6], &[1, 4, 7])
+        );
+    }
+
+    #[test]
+    fn jellyfish() {
+        let board = board_with_rows(
+            9,
+            &[0, 2, 4, 6],
+            &[
+                (0, 0),
+                (0, 1),
+                (2, 1),
+                (2, 2),
+                (4, 2),
+                (4, 3),
+                (6, 3),
+                (6, 0),
+            ],
+        );
+        assert_eq!(Fish { size: 3 }.find(&board), None);
+        let step = Fish { size: 4 }.find(&board).unwrap();
+        assert_eq!(step.technique, TechniqueKind::Jellyfish);
+        assert_eq!(
+            step.eliminations,
+            eliminated_from_cols(9, &[0, 2, 4, 6], &[0, 1, 2, 3])
+        );
+    }
+}
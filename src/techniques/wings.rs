@@ -0,0 +1,131 @@
+use super::{all_empty_cells, candidates_of, sees, SolveStep, Technique, TechniqueKind};
+use crate::Board;
+
+/// A pivot cell with candidates `xy` seeing pincers with `xz` and `yz`:
+/// whichever value the pivot takes, one pincer is `z`, so `z` can be removed
+/// from every cell seeing both pincers
+pub struct XyWing;
+
+/// As an XY-Wing, but the pivot also holds `z`, so only cells seeing the
+/// pivot and both pincers lose `z`
+pub struct XyzWing;
+
+fn find_wing(board: &Board, kind: TechniqueKind, pivot_size: usize) -> Option<SolveStep> {
+    let cells = all_empty_cells(board);
+    let bivalue = cells
+        .iter()
+        .copied()
+        .filter(|cell| candidates_of(board, *cell).len() == 2)
+        .collect::<Vec<(usize, usize)>>();
+    cells
+        .iter()
+        .copied()
+        .filter(|cell| candidates_of(board, *cell).len() == pivot_size)
+        .find_map(|pivot| {
+            let pivot_values = candidates_of(board, pivot);
+            let pincers = bivalue
+                .iter()
+                .copied()
+                .filter(|cell| sees(board, pivot, *cell))
+                .collect::<Vec<(usize, usize)>>();
+            pincers.iter().enumerate().find_map(|(index, &first)| {
+                pincers[index + 1..].iter().find_map(|&second| {
+                    let first_values = candidates_of(board, first);
+                    let second_values = candidates_of(board, second);
+                    // The pincers share exactly one value, z, and each
+                    // shares a different value with the pivot
+                    let common = first_values
+                        .iter()
+                        .copied()
+                        .filter(|value| second_values.contains(value))
+                        .collect::<Vec<i32>>();
+                    let z = match common.as_slice() {
+                        [z] => *z,
+                        _ => return None,
+                    };
+                    let mut expected = first_values
+                        .iter()
+                        .chain(second_values.iter())
+                        .copied()
+                        .filter(|value| *value != z)
+                        .collect::<Vec<i32>>();
+                    if pivot_size == 3 {
+                        expected.push(z);
+                    }
+                    expected.sort();
+                    if expected != pivot_values {
+                        return None;
+                    }
+                    let eliminations = cells
+                        .iter()
+                        .copied()
+                        .filter(|cell| {
+                            *cell != pivot
+                                && sees(board, *cell, first)
+                                && sees(board, *cell, second)
+                                && (pivot_size == 2 || sees(board, *cell, pivot))
+                                && candidates_of(board, *cell).contains(&z)
+                        })
+                        .map(|cell| (cell, z))
+                        .collect();
+                    SolveStep::eliminations(
+                        kind,
+                        vec![pivot, first, second],
+                        expected,
+                        eliminations,
+                    )
+                })
+            })
+        })
+}
+
+impl Technique for XyWing {
+    fn kind(&self) -> TechniqueKind {
+        TechniqueKind::XyWing
+    }
+
+    fn find(&self, board: &Board) -> Option<SolveStep> {
+        find_wing(board, self.kind(), 2)
+    }
+}
+
+impl Technique for XyzWing {
+    fn kind(&self) -> TechniqueKind {
+        TechniqueKind::XyzWing
+    }
+
+    fn find(&self, board: &Board) -> Option<SolveStep> {
+        find_wing(board, self.kind(), 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xy_wing() {
+        let mut board = Board::new();
+        board.set_candidates((0, 0), &[1, 2]);
+        board.set_candidates((0, 5), &[1, 3]);
+        board.set_candidates((5, 0), &[2, 3]);
+        let step = XyWing.find(&board).unwrap();
+        assert_eq!(step.technique, TechniqueKind::XyWing);
+        assert_eq!(step.cells, vec![(0, 0), (0, 5), (5, 0)]);
+        assert_eq!(step.values, vec![1, 2]);
+        assert_eq!(step.eliminations, vec![((5, 5), 3)]);
+    }
+
+    #[test]
+    fn xyz_wing() {
+        let mut board = Board::new();
+        board.set_candidates((0, 0), &[1, 2, 3]);
+        board.set_candidates((0, 5), &[1, 3]);
+        board.set_candidates((1, 1), &[2, 3]);
+        assert_eq!(XyWing.find(&board), None);
+        let step = XyzWing.find(&board).unwrap();
+        assert_eq!(step.technique, TechniqueKind::XyzWing);
+        assert_eq!(step.values, vec![1, 2, 3]);
+        assert_eq!(step.eliminations, vec![((0, 1), 3), ((0, 2), 3)]);
+    }
+}